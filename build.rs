@@ -1,3 +1,7 @@
 fn main() {
-    println!("cargo:rustc-link-arg-bins=-Tlinkall.x");
+    // The linker script only exists for the ESP32 build; the host simulator
+    // links like any other std binary.
+    if std::env::var_os("CARGO_FEATURE_ESP32").is_some() {
+        println!("cargo:rustc-link-arg-bins=-Tlinkall.x");
+    }
 }
@@ -0,0 +1,95 @@
+use embassy_time::{Duration, Timer};
+use esp_hal::gpio::{GpioPin, Level, Output};
+use esp_hal::ledc::{channel, timer, HighSpeed, Ledc};
+use esp_hal::prelude::*;
+
+#[cfg(feature = "buzzer")]
+use super::{melody::Melody, music::Song};
+use super::{music, Sound, SOUNDS};
+
+const BUZZER_PIN: u8 = 33;
+pub struct AudioEffect<'a> {
+    ledc: Ledc<'a>,
+    buzzer: Output<'static>,
+}
+
+impl<'a> AudioEffect<'a> {
+    pub fn new(ledc: Ledc<'a>, buzzer_pin: GpioPin<BUZZER_PIN>) -> Self {
+        let buzzer = Output::new(buzzer_pin, Level::Low);
+
+        Self { ledc, buzzer }
+    }
+
+    pub async fn play_tone(&mut self, note: u32, duration: u32) {
+        if note == music::REST {
+            Timer::after(Duration::from_millis(duration as u64)).await;
+            return;
+        }
+
+        let freq = note.Hz();
+        let mut hstimer0 = self.ledc.timer::<HighSpeed>(timer::Number::Timer0);
+        hstimer0
+            .configure(timer::config::Config {
+                duty: timer::config::Duty::Duty10Bit,
+                // clock_source: timer::LSClockSource::APBClk,
+                clock_source: timer::HSClockSource::APBClk,
+                frequency: freq,
+            })
+            .unwrap();
+
+        let mut channel0 = self
+            .ledc
+            .channel(channel::Number::Channel0, &mut self.buzzer);
+        channel0
+            .configure(channel::config::Config {
+                timer: &hstimer0,
+                duty_pct: 50,
+                pin_config: channel::config::PinConfig::PushPull,
+            })
+            .unwrap();
+
+        Timer::after(Duration::from_millis(duration as u64)).await;
+        channel0.set_duty(0).unwrap();
+    }
+
+    /// Plays `melody` note by note. Stops early and returns the sound that
+    /// was requested in the meantime, if any.
+    #[cfg(feature = "buzzer")]
+    pub async fn play_melody(&mut self, melody: &Melody) -> Option<Sound> {
+        let song = Song::new(melody.tempo);
+
+        for &(note, divider) in melody.notes {
+            if let Ok(sound) = SOUNDS.try_receive() {
+                return Some(sound);
+            }
+
+            let duration = song.calc_note_duration(divider);
+            // Sound 90% of the note so repeated notes don't blend together
+            let tone = duration * 9 / 10;
+            self.play_tone(note, tone).await;
+            Timer::after(Duration::from_millis((duration - tone) as u64)).await;
+        }
+
+        None
+    }
+}
+
+/// Plays the sounds requested with [`play_tone`](super::play_tone), one after
+/// the other, so the game loop never waits for the buzzer.
+#[embassy_executor::task]
+pub async fn audio_task(mut audio: AudioEffect<'static>) {
+    let mut interrupted_by = None;
+
+    loop {
+        let sound = match interrupted_by.take() {
+            Some(sound) => sound,
+            None => SOUNDS.receive().await,
+        };
+
+        match sound {
+            Sound::Tone { note, duration } => audio.play_tone(note, duration).await,
+            #[cfg(feature = "buzzer")]
+            Sound::Melody(melody) => interrupted_by = audio.play_melody(melody).await,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Tunes for the title screen, level ups and game over.
+
+use super::music::*;
+
+/// A tune played through [`Song`].
+///
+/// Each note comes with its length as a divider of a whole note: 4 is a
+/// quarter note, 8 an eighth, and negative values are dotted (one and a half
+/// times as long).
+pub struct Melody {
+    /// Beats per minute.
+    pub tempo: u16,
+    pub notes: &'static [(u32, i16)],
+}
+
+pub const TITLE_THEME: Melody = Melody {
+    tempo: 120,
+    notes: &[
+        (NOTE_A4, 8),
+        (NOTE_C5, 8),
+        (NOTE_E5, 4),
+        (NOTE_D5, 8),
+        (NOTE_C5, 8),
+        (NOTE_D5, 4),
+        (NOTE_E5, -4),
+        (NOTE_A4, 8),
+        (NOTE_G4, 4),
+        (NOTE_A4, 2),
+    ],
+};
+
+pub const LEVEL_COMPLETE: Melody = Melody {
+    tempo: 180,
+    notes: &[
+        (NOTE_C5, 8),
+        (NOTE_E5, 8),
+        (NOTE_G5, 8),
+        (NOTE_C6, -4),
+        (NOTE_G5, 8),
+        (NOTE_C6, 2),
+    ],
+};
+
+pub const GAME_OVER: Melody = Melody {
+    tempo: 100,
+    notes: &[
+        (NOTE_C5, 4),
+        (NOTE_B4, 4),
+        (NOTE_AS4, 4),
+        (REST, 8),
+        (NOTE_A4, -2),
+    ],
+};
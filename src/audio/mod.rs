@@ -1,51 +1,64 @@
-use esp_hal::delay::Delay;
-use esp_hal::gpio::{GpioPin, Level, Output};
-use esp_hal::ledc::{channel, timer, HighSpeed, Ledc};
-use esp_hal::prelude::*;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+
+#[cfg(feature = "esp32")]
+mod buzzer;
+#[cfg(feature = "buzzer")]
+pub mod melody;
 pub mod music;
 
-const BUZZER_PIN: u8 = 33;
-pub struct AudioEffect<'a> {
-    ledc: Ledc<'a>,
-    buzzer: Output<'static>,
-    delay: Delay,
+#[cfg(feature = "esp32")]
+pub use buzzer::{audio_task, AudioEffect};
+#[cfg(feature = "buzzer")]
+use melody::Melody;
+
+// Sounds requested within a frame or two; more than that are dropped
+const SOUND_QUEUE_SIZE: usize = 8;
+
+#[derive(Clone, Copy)]
+pub enum Sound {
+    Tone {
+        /// Frequency in Hz, see [`music`].
+        note: u32,
+        /// Length in milliseconds.
+        duration: u32,
+    },
+    #[cfg(feature = "buzzer")]
+    Melody(&'static Melody),
 }
 
-impl<'a> AudioEffect<'a> {
-    pub fn new(ledc: Ledc<'a>, buzzer_pin: GpioPin<BUZZER_PIN>) -> Self {
-        let buzzer = Output::new(buzzer_pin, Level::Low);
+static SOUNDS: Channel<CriticalSectionRawMutex, Sound, SOUND_QUEUE_SIZE> = Channel::new();
+static ENABLED: AtomicBool = AtomicBool::new(true);
 
-        Self {
-            ledc,
-            buzzer,
-            delay: Delay::new(),
-        }
+/// Turns all sounds on or off. Sounds requested while off are dropped.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Queues a tone for the audio task and returns immediately.
+///
+/// The tone is dropped if the queue is full, or never played when no audio
+/// task runs (host simulator).
+pub fn play_tone(note: u32, duration: u32) {
+    if !is_enabled() {
+        return;
     }
+    let _ = SOUNDS.try_send(Sound::Tone { note, duration });
+}
 
-    pub fn play_tone(&mut self, note: u32, duration: u32) {
-        let freq = note.Hz();
-        let mut hstimer0 = self.ledc.timer::<HighSpeed>(timer::Number::Timer0);
-        hstimer0
-            .configure(timer::config::Config {
-                duty: timer::config::Duty::Duty10Bit,
-                // clock_source: timer::LSClockSource::APBClk,
-                clock_source: timer::HSClockSource::APBClk,
-                frequency: freq,
-            })
-            .unwrap();
-
-        let mut channel0 = self
-            .ledc
-            .channel(channel::Number::Channel0, &mut self.buzzer);
-        channel0
-            .configure(channel::config::Config {
-                timer: &hstimer0,
-                duty_pct: 50,
-                pin_config: channel::config::PinConfig::PushPull,
-            })
-            .unwrap();
-
-        self.delay.delay_millis(duration);
-        channel0.set_duty(0).unwrap();
+/// Queues a melody for the audio task and returns immediately.
+///
+/// Any sound requested while it plays cuts it short, so sound effects stay in
+/// sync with the game.
+#[cfg(feature = "buzzer")]
+pub fn play_melody(melody: &'static Melody) {
+    if !is_enabled() {
+        return;
     }
+    let _ = SOUNDS.try_send(Sound::Melody(melody));
 }
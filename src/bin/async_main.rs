@@ -1,15 +1,31 @@
 #![no_std]
 #![no_main]
 
-use cosmic_yudh::{audio::AudioEffect, control, game::Game};
+use cosmic_yudh::{
+    audio::{self, AudioEffect},
+    control,
+    display::PartialFlushDisplay,
+    game::Game,
+    highscore::FlashScoreStorage,
+    replay::Recorder,
+    rng::XorShift32,
+};
 use embassy_executor::Spawner;
 use esp_backtrace as _;
 use esp_hal::{ledc::Ledc, prelude::*, rng::Rng};
+use esp_println::{print, println};
+use esp_storage::FlashStorage;
 use log::info;
 use ssd1306::{
-    mode::DisplayConfigAsync, prelude::DisplayRotation, size::DisplaySize128x64,
-    I2CDisplayInterface, Ssd1306Async,
+    prelude::DisplayRotation, size::DisplaySize128x64, I2CDisplayInterface, Ssd1306Async,
 };
+use static_cell::StaticCell;
+
+static RECORDER: StaticCell<Recorder> = StaticCell::new();
+
+// The `highscores` partition in partitions.csv
+const HIGH_SCORE_OFFSET: u32 = 0x3f0000;
+const HIGH_SCORE_SIZE: u32 = 0x4000;
 
 #[main]
 async fn main(spawner: Spawner) {
@@ -26,18 +42,37 @@ async fn main(spawner: Spawner) {
 
     info!("Embassy initialized!");
 
-    // Track Joystick movements in background
+    #[cfg(feature = "joystick")]
+    {
+        // Track Joystick movements in background
+        spawner
+            .spawn(control::track_joystick(
+                peripherals.GPIO13,
+                peripherals.GPIO14,
+                peripherals.ADC2,
+                control::DEFAULT_DEAD_ZONE,
+            ))
+            .unwrap();
+
+        // Track Joystick button state
+        spawner
+            .spawn(control::button_press(peripherals.GPIO32))
+            .unwrap();
+    }
+
+    // Track the up, down and fire buttons in background
+    #[cfg(feature = "buttons")]
     spawner
-        .spawn(control::track_joystick(
-            peripherals.GPIO13,
-            peripherals.GPIO14,
-            peripherals.ADC2,
+        .spawn(control::track_buttons(
+            peripherals.GPIO19,
+            peripherals.GPIO21,
+            peripherals.GPIO32,
         ))
         .unwrap();
 
-    // Track Joystick button state
+    // Track the pause button, the same for both control schemes
     spawner
-        .spawn(control::button_press(peripherals.GPIO32))
+        .spawn(control::pause_button(peripherals.GPIO25))
         .unwrap();
 
     // Initialize the OLED Display
@@ -52,17 +87,39 @@ async fn main(spawner: Spawner) {
     .with_sda(peripherals.GPIO23)
     .into_async();
     let interface = I2CDisplayInterface::new(i2c);
-    // initialize the display
-    let mut display = Ssd1306Async::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
-        .into_buffered_graphics_mode();
-    display.init().await.unwrap();
+    // initialize the display, only the parts of the frame that changed are
+    // sent to it
+    let display = Ssd1306Async::new(interface, DisplaySize128x64, DisplayRotation::Rotate0);
+    let display = PartialFlushDisplay::new(display).await.unwrap();
 
-    // sound effects module
+    // Play sound effects in background
     let ledc = Ledc::new(peripherals.LEDC);
-    let audio = AudioEffect::new(ledc, peripherals.GPIO33);
+    let buzzer = AudioEffect::new(ledc, peripherals.GPIO33);
+    spawner.spawn(audio::audio_task(buzzer)).unwrap();
+
+    // Gameplay randomness is seeded once from the hardware RNG
+    let mut hw_rng = Rng::new(peripherals.RNG);
+    let rng = XorShift32::from_rng(&mut hw_rng);
+    // Record every run so it can be replayed on the host
+    let recorder = RECORDER.init(Recorder::new());
+
+    let mut score_storage =
+        FlashScoreStorage::new(FlashStorage::new(), HIGH_SCORE_OFFSET, HIGH_SCORE_SIZE);
 
-    let rng = Rng::new(peripherals.RNG);
     // Initialize the Game
-    let mut game = Game::new(display, rng, audio);
+    let mut game = Game::new(display, rng)
+        .record(recorder, print_recording)
+        .high_scores(&mut score_storage);
     game.start().await;
 }
+
+fn print_recording(recorder: &Recorder) {
+    println!("Input log of the last run, save the next line and pass it to the replay tool:");
+    for byte in recorder.as_bytes() {
+        print!("{byte:02x}");
+    }
+    println!();
+    if recorder.is_truncated() {
+        println!("The log filled up and misses the end of the run");
+    }
+}
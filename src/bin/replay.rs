@@ -0,0 +1,66 @@
+//! Replays an input log recorded by the game and reports how the run ended.
+//!
+//! Usage: `replay <log>`. The log can be the binary file written by
+//! `sim --record` or the hex dump the ESP32 prints on the serial console.
+
+use std::{env, fs, process};
+
+use cosmic_yudh::{replay::Replay, rng::XorShift32, world::World};
+
+// Size of the OLED the logs were recorded on
+const SCREEN_WIDTH: i32 = 128;
+const SCREEN_HEIGHT: i32 = 64;
+
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: replay <log>");
+        process::exit(2);
+    };
+
+    let contents = fs::read(&path).unwrap_or_else(|err| {
+        eprintln!("{path}: {err}");
+        process::exit(1);
+    });
+    let bytes = decode_hex(&contents).unwrap_or(contents);
+
+    let replay = Replay::parse(&bytes).unwrap_or_else(|err| {
+        eprintln!("{path}: invalid log: {err:?}");
+        process::exit(1);
+    });
+    let seed = replay.seed();
+    if replay.is_truncated() {
+        eprintln!("{path}: the log filled up, the run went on past its end");
+    }
+
+    let mut world = World::new(SCREEN_WIDTH, SCREEN_HEIGHT, XorShift32::new(seed));
+    let mut frames = 0;
+    for input in replay {
+        world.step(input);
+        frames += 1;
+    }
+
+    println!("seed:   {seed:#010x}");
+    println!("frames: {frames}");
+    println!("state:  {:?}", world.state());
+    println!("score:  {}", world.score());
+    println!("level:  {}", world.level());
+    println!("lives:  {}", world.lives());
+}
+
+/// Decodes a hex dump, ignoring whitespace. `None` if it isn't one.
+fn decode_hex(contents: &[u8]) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = contents
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+
+    digits
+        .chunks_exact(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
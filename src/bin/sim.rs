@@ -0,0 +1,217 @@
+//! Desktop simulator: runs the game against an in-memory framebuffer.
+//!
+//! Controls: W/Up and S/Down move the ship, A/Left and D/Right move it back
+//! and forth, Space/Enter fires, P pauses, Q/Esc quits.
+//!
+//! Frames are drawn in the terminal. Pass `--frames <dir>` to also write every
+//! flushed frame to `<dir>` as a PBM image, and `--seed <n>` to play a fixed
+//! enemy pattern instead of a random one.
+//!
+//! `--record <file>` saves the input log of each run to `<file>` when the
+//! player dies; `--replay <file>` plays such a log back (see the `replay` bin
+//! for a headless version).
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        OnceLock,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use cosmic_yudh::{
+    game::Game,
+    highscore::{FlashScoreStorage, MemoryFlash},
+    input::{self, InputEventKind},
+    player::{AXIS_MAX, PLAYER_X_AXIS, PLAYER_Y_AXIS},
+    replay::{Recorder, Replay},
+    rng::XorShift32,
+    sim::{SimDisplay, HEIGHT, WIDTH},
+};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue, terminal,
+};
+use embassy_executor::Spawner;
+
+static FRAME_DIR: OnceLock<PathBuf> = OnceLock::new();
+static RECORD_PATH: OnceLock<PathBuf> = OnceLock::new();
+static FRAME_COUNT: AtomicU32 = AtomicU32::new(0);
+
+// High scores are kept in emulated flash of this size for the session
+const SCORE_FLASH_SIZE: usize = 2 * 4096;
+
+// Terminals report key repeats but not releases, so a direction stays held
+// until no key event arrived for this long.
+const KEY_HOLD: Duration = Duration::from_millis(150);
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+
+    let mut replay = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--frames", Some(dir)) => {
+                let dir = PathBuf::from(dir);
+                fs::create_dir_all(&dir).unwrap();
+                FRAME_DIR.set(dir).unwrap();
+            }
+            ("--seed", Some(value)) => seed = value.parse().unwrap_or_else(|_| usage()),
+            ("--record", Some(path)) => RECORD_PATH.set(PathBuf::from(path)).unwrap(),
+            ("--replay", Some(path)) => {
+                // The game borrows the log for as long as it runs
+                let log: &'static [u8] = fs::read(path).unwrap().leak();
+                replay = Some(Replay::parse(log).unwrap());
+            }
+            _ => usage(),
+        }
+    }
+
+    terminal::enable_raw_mode().unwrap();
+    execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide).unwrap();
+
+    // Keyboard input in background
+    thread::spawn(read_keyboard);
+
+    if let Some(replay) = &replay {
+        seed = replay.seed();
+    }
+
+    let mut score_storage = FlashScoreStorage::new(
+        MemoryFlash::<SCORE_FLASH_SIZE>::new(),
+        0,
+        SCORE_FLASH_SIZE as u32,
+    );
+
+    let display = SimDisplay::new(present);
+    let mut game = Game::new(display, XorShift32::new(seed)).high_scores(&mut score_storage);
+    if RECORD_PATH.get().is_some() {
+        game = game.record(Box::leak(Box::default()), save_recording);
+    }
+    if let Some(replay) = replay {
+        game = game.replay(replay);
+    }
+    game.start().await;
+}
+
+fn usage() -> ! {
+    eprintln!("usage: sim [--frames <dir>] [--seed <n>] [--record <file>] [--replay <file>]");
+    std::process::exit(2);
+}
+
+fn read_keyboard() {
+    let mut last_x_key: Option<Instant> = None;
+    let mut last_y_key: Option<Instant> = None;
+
+    loop {
+        if event::poll(Duration::from_millis(10)).unwrap() {
+            if let Event::Key(key) = event::read().unwrap() {
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('w') => {
+                        PLAYER_Y_AXIS.store(-AXIS_MAX, Ordering::Relaxed);
+                        last_y_key = Some(Instant::now());
+                    }
+                    KeyCode::Down | KeyCode::Char('s') => {
+                        PLAYER_Y_AXIS.store(AXIS_MAX, Ordering::Relaxed);
+                        last_y_key = Some(Instant::now());
+                    }
+                    KeyCode::Left | KeyCode::Char('a') => {
+                        PLAYER_X_AXIS.store(-AXIS_MAX, Ordering::Relaxed);
+                        last_x_key = Some(Instant::now());
+                    }
+                    KeyCode::Right | KeyCode::Char('d') => {
+                        PLAYER_X_AXIS.store(AXIS_MAX, Ordering::Relaxed);
+                        last_x_key = Some(Instant::now());
+                    }
+                    // Terminals report no releases, so every key press is a
+                    // short tap of the button (holding the key repeats them)
+                    KeyCode::Char(' ') | KeyCode::Enter => {
+                        let now = embassy_time::Instant::now();
+                        input::publish(InputEventKind::Pressed, now);
+                        input::publish(InputEventKind::Released, now);
+                    }
+                    KeyCode::Char('p') => {
+                        input::publish(InputEventKind::Pause, embassy_time::Instant::now())
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => quit(),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => quit(),
+                    _ => {}
+                }
+            }
+        }
+
+        if last_x_key.is_some_and(|at| at.elapsed() > KEY_HOLD) {
+            PLAYER_X_AXIS.store(0, Ordering::Relaxed);
+            last_x_key = None;
+        }
+        if last_y_key.is_some_and(|at| at.elapsed() > KEY_HOLD) {
+            PLAYER_Y_AXIS.store(0, Ordering::Relaxed);
+            last_y_key = None;
+        }
+    }
+}
+
+fn quit() -> ! {
+    execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen).unwrap();
+    terminal::disable_raw_mode().unwrap();
+    std::process::exit(0);
+}
+
+fn save_recording(recorder: &Recorder) {
+    if let Some(path) = RECORD_PATH.get() {
+        fs::write(path, recorder.as_bytes()).unwrap();
+    }
+}
+
+fn present(display: &SimDisplay) {
+    if let Some(dir) = FRAME_DIR.get() {
+        write_pbm(dir, display).unwrap();
+    }
+    draw_terminal(display).unwrap();
+}
+
+fn write_pbm(dir: &std::path::Path, display: &SimDisplay) -> io::Result<()> {
+    let frame = FRAME_COUNT.fetch_add(1, Ordering::Relaxed);
+    let mut file = fs::File::create(dir.join(format!("frame{frame:06}.pbm")))?;
+
+    write!(file, "P4\n{WIDTH} {HEIGHT}\n")?;
+    // PBM treats set bits as black; invert so lit pixels show up white like
+    // on the OLED.
+    let pixels: Vec<u8> = display.buffer().iter().map(|byte| !byte).collect();
+    file.write_all(&pixels)
+}
+
+fn draw_terminal(display: &SimDisplay) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    queue!(stdout, cursor::MoveTo(0, 0))?;
+
+    // Two pixel rows per terminal line using half blocks
+    for y in (0..HEIGHT).step_by(2) {
+        let line: String = (0..WIDTH)
+            .map(|x| match (display.pixel(x, y), display.pixel(x, y + 1)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            })
+            .collect();
+        write!(stdout, "{line}\r\n")?;
+    }
+
+    stdout.flush()
+}
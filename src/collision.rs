@@ -0,0 +1,235 @@
+//! Collision tests.
+//!
+//! Shapes collide when their bounding boxes overlap. With the
+//! `pixel-collision` feature, tests against a sprite only count the pixels it
+//! actually sets, so a bullet grazing an empty corner of the player's jet
+//! misses.
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::{Circle, Rectangle},
+};
+
+/// Set pixels of a 1 bpp sprite, read from the same data as its `ImageRaw`.
+#[derive(Clone, Copy, Debug)]
+pub struct Mask {
+    data: &'static [u8],
+    width: u32,
+    height: u32,
+}
+
+impl Mask {
+    /// `data` holds rows of `width` pixels, each padded to a whole byte,
+    /// most significant bit first.
+    pub const fn new(data: &'static [u8], width: u32) -> Self {
+        let height = data.len() as u32 / row_len(width);
+        Self {
+            data,
+            width,
+            height,
+        }
+    }
+
+    /// Whether the pixel at `(x, y)` of the sprite is set. Pixels outside
+    /// the sprite are not.
+    pub const fn is_set(&self, x: i32, y: i32) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+
+        let byte = self.data[y as usize * row_len(self.width) as usize + x as usize / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+}
+
+const fn row_len(width: u32) -> u32 {
+    width.div_ceil(8)
+}
+
+/// A shape tested against sprites: bullets, beams and bounding boxes.
+#[derive(Clone, Copy, Debug)]
+pub enum Hitbox {
+    Rectangle(Rectangle),
+    Circle(Circle),
+}
+
+impl From<Rectangle> for Hitbox {
+    fn from(rectangle: Rectangle) -> Self {
+        Hitbox::Rectangle(rectangle)
+    }
+}
+
+impl From<Circle> for Hitbox {
+    fn from(circle: Circle) -> Self {
+        Hitbox::Circle(circle)
+    }
+}
+
+impl Hitbox {
+    const fn bounding_box(&self) -> Rectangle {
+        match *self {
+            Hitbox::Rectangle(rectangle) => rectangle,
+            Hitbox::Circle(circle) => {
+                Rectangle::new(circle.top_left, Size::new(circle.diameter, circle.diameter))
+            }
+        }
+    }
+
+    /// Same pixels as `embedded_graphics` draws for the shape.
+    const fn contains(&self, x: i32, y: i32) -> bool {
+        match *self {
+            Hitbox::Rectangle(rectangle) => {
+                let Point { x: left, y: top } = rectangle.top_left;
+                x >= left
+                    && y >= top
+                    && x < left + rectangle.size.width as i32
+                    && y < top + rectangle.size.height as i32
+            }
+            Hitbox::Circle(circle) => {
+                // Twice the distance from the centre, to stay in integers
+                let radius_2x = circle.diameter.saturating_sub(1) as i32;
+                let dx = circle.top_left.x * 2 + radius_2x - x * 2;
+                let dy = circle.top_left.y * 2 + radius_2x - y * 2;
+
+                let diameter = circle.diameter;
+                let threshold = if diameter <= 4 {
+                    diameter * diameter - diameter / 2
+                } else {
+                    diameter * diameter
+                };
+                ((dx * dx + dy * dy) as u32) < threshold
+            }
+        }
+    }
+}
+
+pub fn detect_collison(a: Rectangle, b: Rectangle) -> bool {
+    let intersection = a.intersection(&b);
+
+    if intersection.size.width == 0 || intersection.size.height == 0 {
+        return false;
+    }
+
+    true
+}
+
+/// Whether `hitbox` touches the sprite of `mask` drawn at `top_left`.
+pub const fn sprite_hit(mask: &Mask, top_left: Point, hitbox: Hitbox) -> bool {
+    let bounds = hitbox.bounding_box();
+
+    // Overlap of the two bounding boxes
+    let left = max(top_left.x, bounds.top_left.x);
+    let top = max(top_left.y, bounds.top_left.y);
+    let right = min(
+        top_left.x + mask.width as i32,
+        bounds.top_left.x + bounds.size.width as i32,
+    );
+    let bottom = min(
+        top_left.y + mask.height as i32,
+        bounds.top_left.y + bounds.size.height as i32,
+    );
+
+    if left >= right || top >= bottom {
+        return false;
+    }
+    if !cfg!(feature = "pixel-collision") {
+        return true;
+    }
+
+    let mut y = top;
+    while y < bottom {
+        let mut x = left;
+        while x < right {
+            if mask.is_set(x - top_left.x, y - top_left.y) && hitbox.contains(x, y) {
+                return true;
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+
+    false
+}
+
+const fn max(a: i32, b: i32) -> i32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+const fn min(a: i32, b: i32) -> i32 {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sprites::{MASK_FIGHTER, MASK_PLANET_KILLER, MASK_PLAYER_JET};
+
+    // Hitboxes inside the bounding box that miss every set pixel only miss
+    // with pixel-collision
+    const NEAR_MISS_HITS: bool = !cfg!(feature = "pixel-collision");
+
+    fn circle(x: i32, y: i32, diameter: u32) -> Hitbox {
+        Hitbox::Circle(Circle::new(Point::new(x, y), diameter))
+    }
+
+    fn rectangle(x: i32, y: i32, width: u32, height: u32) -> Hitbox {
+        Hitbox::Rectangle(Rectangle::new(Point::new(x, y), Size::new(width, height)))
+    }
+
+    // The top right corner of the jet is empty above row 3 and right of
+    // column 11
+    #[test]
+    fn shots_near_the_jet() {
+        let hits = |hitbox| sprite_hit(&MASK_PLAYER_JET, Point::zero(), hitbox);
+
+        assert_eq!(hits(circle(12, -1, 5)), NEAR_MISS_HITS);
+        assert_eq!(hits(circle(13, 0, 5)), NEAR_MISS_HITS);
+        assert!(hits(circle(12, 5, 5)));
+        assert!(hits(circle(9, 0, 5)));
+        assert_eq!(hits(rectangle(12, 4, 5, 2)), NEAR_MISS_HITS);
+        assert!(hits(rectangle(13, 7, 5, 2)));
+    }
+
+    #[test]
+    fn bullets_against_an_enemy() {
+        let top_left = Point::new(40, 20);
+        let hits = |x, y, diameter| {
+            let hitbox = circle(top_left.x + x, top_left.y + y, diameter);
+            sprite_hit(&MASK_PLANET_KILLER, top_left, hitbox)
+        };
+
+        assert!(hits(10, 10, 3));
+        // Empty corner and the hole in the planet killer
+        assert_eq!(hits(0, 0, 3), NEAR_MISS_HITS);
+        assert_eq!(hits(15, 7, 2), NEAR_MISS_HITS);
+        // Outside its bounding box
+        assert!(!hits(24, 0, 3));
+        assert!(!hits(-3, 10, 3));
+    }
+
+    #[test]
+    fn beams_against_an_enemy() {
+        let top_left = Point::new(100, 8);
+        let hits = |x, y, width, height| {
+            let hitbox = rectangle(top_left.x + x, top_left.y + y, width, height);
+            sprite_hit(&MASK_FIGHTER, top_left, hitbox)
+        };
+
+        assert!(hits(0, 7, 2, 2));
+        assert!(hits(-20, 8, 21, 1));
+        // Empty corner and the gap behind a wing tip
+        assert_eq!(hits(0, 0, 4, 4), NEAR_MISS_HITS);
+        assert_eq!(hits(12, 4, 3, 1), NEAR_MISS_HITS);
+        // Outside its bounding box
+        assert!(!hits(16, 0, 4, 16));
+        assert!(!hits(0, 16, 16, 2));
+    }
+}
@@ -1,63 +1,238 @@
 use core::sync::atomic::Ordering;
 
-use embassy_time::{Duration, Timer};
+use embassy_time::{Duration, Instant, Timer};
+use esp_hal::gpio::{GpioPin, Input, Pull};
+#[cfg(feature = "joystick")]
 use esp_hal::{
     analog::adc::{Adc, AdcConfig, Attenuation},
-    gpio::{GpioPin, Input, Pull},
     peripherals::ADC2,
     prelude::nb,
 };
 
 use crate::{
-    game,
-    player::{PlayerDirection, PLAYER_DIRECTION},
+    input::{self, ButtonTracker, DirectionTracker, InputEventKind},
+    player::{AXIS_MAX, PLAYER_X_AXIS, PLAYER_Y_AXIS},
 };
 
+#[cfg(all(feature = "joystick", feature = "buttons"))]
+compile_error!("the `joystick` and `buttons` features are mutually exclusive");
+#[cfg(not(any(feature = "joystick", feature = "buttons")))]
+compile_error!("enable either the `joystick` or the `buttons` feature");
+
+#[cfg(feature = "joystick")]
 const VRX_PIN: u8 = 13;
+#[cfg(feature = "joystick")]
 const VRY_PIN: u8 = 14;
 
+/// Readings this close to the resting position count as centred. Raise it
+/// if a worn stick makes the ship drift.
+#[cfg(feature = "joystick")]
+pub const DEFAULT_DEAD_ZONE: u16 = 200;
+// 12-bit ADC
+#[cfg(feature = "joystick")]
+const ADC_MAX: u16 = 4095;
+#[cfg(feature = "joystick")]
+const CALIBRATION_SAMPLES: u32 = 16;
+
 const MAIN_BTN_PIN: u8 = 32;
+const PAUSE_BTN_PIN: u8 = 25;
 
-// #[cfg(feature = "buttons")]
-// const UP_BTN_PIN: u8 = 18;
-// #[cfg(feature = "buttons")]
-// const DOWN_BTN_PIN: u8 = 21;
+#[cfg(feature = "buttons")]
+const UP_BTN_PIN: u8 = 19;
+#[cfg(feature = "buttons")]
+const DOWN_BTN_PIN: u8 = 21;
 
+// Buttons are sampled this often, and must read the same for
+// `DEBOUNCE_SAMPLES` samples in a row before a press or release counts.
+const BUTTON_POLL_INTERVAL: Duration = Duration::from_millis(5);
+const DEBOUNCE_SAMPLES: u8 = 4;
+
+/// Tracks the joystick position. The stick must be at rest at boot, when its
+/// centre is calibrated.
+#[cfg(feature = "joystick")]
 #[embassy_executor::task]
-pub async fn track_joystick(vrx: GpioPin<VRX_PIN>, _vry: GpioPin<VRY_PIN>, adc: ADC2) {
+pub async fn track_joystick(
+    vrx: GpioPin<VRX_PIN>,
+    vry: GpioPin<VRY_PIN>,
+    adc: ADC2,
+    dead_zone: u16,
+) {
     let mut adc2_config = AdcConfig::new();
     let mut vrx_pin = adc2_config.enable_pin(vrx, Attenuation::Attenuation11dB);
-    // let mut vry_pin = adc2_config.enable_pin(vry, Attenuation::Attenuation11dB);
+    let mut vry_pin = adc2_config.enable_pin(vry, Attenuation::Attenuation11dB);
 
     let mut adc2 = Adc::new(adc, adc2_config);
 
-    loop {
-        let Ok(adc_value): Result<u16, _> = nb::block!(adc2.read_oneshot(&mut vrx_pin)) else {
-            continue;
-        };
+    // Average a few readings of the resting position
+    let (mut x_sum, mut y_sum) = (0, 0);
+    let mut samples = 0;
+    while samples < CALIBRATION_SAMPLES {
+        let x_reading: Result<u16, _> = nb::block!(adc2.read_oneshot(&mut vrx_pin));
+        let y_reading: Result<u16, _> = nb::block!(adc2.read_oneshot(&mut vry_pin));
+        if let (Ok(x_value), Ok(y_value)) = (x_reading, y_reading) {
+            x_sum += x_value as u32;
+            y_sum += y_value as u32;
+            samples += 1;
+        }
+        Timer::after(Duration::from_millis(5)).await;
+    }
+    let x_center = (x_sum / CALIBRATION_SAMPLES) as u16;
+    let y_center = (y_sum / CALIBRATION_SAMPLES) as u16;
 
-        if adc_value < 1500 {
-            PLAYER_DIRECTION.store(PlayerDirection::Up, Ordering::Relaxed);
-        } else if adc_value > 3000 {
-            PLAYER_DIRECTION.store(PlayerDirection::Down, Ordering::Relaxed);
-        } else {
-            PLAYER_DIRECTION.store(PlayerDirection::Idle, Ordering::Relaxed);
+    let mut direction = DirectionTracker::new();
+
+    loop {
+        if let Ok(adc_value) = nb::block!(adc2.read_oneshot(&mut vrx_pin)) {
+            // Low readings are up
+            let value = axis_value(adc_value, x_center, dead_zone);
+            PLAYER_Y_AXIS.store(value, Ordering::Relaxed);
         }
+        if let Ok(adc_value) = nb::block!(adc2.read_oneshot(&mut vry_pin)) {
+            // High readings are forward
+            let value = axis_value(adc_value, y_center, dead_zone);
+            PLAYER_X_AXIS.store(value, Ordering::Relaxed);
+        }
+
+        direction.update(
+            PLAYER_X_AXIS.load(Ordering::Relaxed),
+            PLAYER_Y_AXIS.load(Ordering::Relaxed),
+            Instant::now(),
+        );
 
         Timer::after(Duration::from_millis(50)).await;
     }
 }
 
+/// Maps an ADC reading to an axis value. Readings within `dead_zone` of
+/// `center` are 0, the remaining travel on either side is scaled to
+/// `±AXIS_MAX`.
+#[cfg(feature = "joystick")]
+fn axis_value(adc_value: u16, center: u16, dead_zone: u16) -> i8 {
+    let offset = adc_value as i32 - center as i32;
+    let dead_zone = dead_zone as i32;
+
+    if offset.abs() <= dead_zone {
+        return 0;
+    }
+
+    let travel = if offset < 0 {
+        center as i32
+    } else {
+        (ADC_MAX - center) as i32
+    } - dead_zone;
+
+    let value = ((offset.abs() - dead_zone) * AXIS_MAX as i32 / travel.max(1)).min(AXIS_MAX as i32);
+    (value * offset.signum()) as i8
+}
+
+/// Publishes the events of the joystick button, see
+/// [`input`](crate::input).
+#[cfg(feature = "joystick")]
 #[embassy_executor::task]
 pub async fn button_press(btn: GpioPin<MAIN_BTN_PIN>) {
     let input_btn = Input::new(btn, Pull::Up);
 
+    let mut debouncer = Debouncer::new();
+    let mut tracker = ButtonTracker::new();
+
+    loop {
+        debouncer.update(input_btn.is_low());
+        tracker.update(debouncer.is_pressed(), Instant::now());
+
+        Timer::after(BUTTON_POLL_INTERVAL).await;
+    }
+}
+
+/// Tracks discrete up, down and fire buttons, wired between the pin and GND.
+#[cfg(feature = "buttons")]
+#[embassy_executor::task]
+pub async fn track_buttons(
+    up: GpioPin<UP_BTN_PIN>,
+    down: GpioPin<DOWN_BTN_PIN>,
+    fire: GpioPin<MAIN_BTN_PIN>,
+) {
+    let up_btn = Input::new(up, Pull::Up);
+    let down_btn = Input::new(down, Pull::Up);
+    let fire_btn = Input::new(fire, Pull::Up);
+
+    let mut up = Debouncer::new();
+    let mut down = Debouncer::new();
+    let mut fire = Debouncer::new();
+
+    let mut fire_tracker = ButtonTracker::new();
+    let mut direction = DirectionTracker::new();
+
     loop {
-        if input_btn.is_low() {
-            game::BUTTON_PRESSED.swap(true, Ordering::Relaxed);
-            Timer::after(Duration::from_millis(100)).await;
+        up.update(up_btn.is_low());
+        down.update(down_btn.is_low());
+
+        let y_axis = match (up.is_pressed(), down.is_pressed()) {
+            (true, false) => -AXIS_MAX,
+            (false, true) => AXIS_MAX,
+            _ => 0,
+        };
+        PLAYER_Y_AXIS.store(y_axis, Ordering::Relaxed);
+
+        fire.update(fire_btn.is_low());
+
+        let now = Instant::now();
+        direction.update(0, y_axis, now);
+        fire_tracker.update(fire.is_pressed(), now);
+
+        Timer::after(BUTTON_POLL_INTERVAL).await;
+    }
+}
+
+/// Publishes a [`Pause`](InputEventKind::Pause) event whenever the pause
+/// button, wired between the pin and GND, goes down.
+#[embassy_executor::task]
+pub async fn pause_button(btn: GpioPin<PAUSE_BTN_PIN>) {
+    let input_btn = Input::new(btn, Pull::Up);
+
+    let mut debouncer = Debouncer::new();
+
+    loop {
+        if debouncer.update(input_btn.is_low()) && debouncer.is_pressed() {
+            input::publish(InputEventKind::Pause, Instant::now());
         }
 
-        Timer::after(Duration::from_millis(50)).await;
+        Timer::after(BUTTON_POLL_INTERVAL).await;
+    }
+}
+
+/// Filters out contact bounce by only accepting a new button state once it
+/// has been read `DEBOUNCE_SAMPLES` times in a row.
+struct Debouncer {
+    pressed: bool,
+    stable_samples: u8,
+}
+
+impl Debouncer {
+    fn new() -> Self {
+        Self {
+            pressed: false,
+            stable_samples: 0,
+        }
+    }
+
+    /// Feeds a raw reading. Returns true when the debounced state changed.
+    fn update(&mut self, raw_pressed: bool) -> bool {
+        if raw_pressed == self.pressed {
+            self.stable_samples = 0;
+            return false;
+        }
+
+        self.stable_samples += 1;
+        if self.stable_samples < DEBOUNCE_SAMPLES {
+            return false;
+        }
+
+        self.pressed = raw_pressed;
+        self.stable_samples = 0;
+        true
+    }
+
+    fn is_pressed(&self) -> bool {
+        self.pressed
     }
 }
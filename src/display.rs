@@ -0,0 +1,349 @@
+use core::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+#[cfg(feature = "esp32")]
+use esp_hal::i2c::master::I2c;
+#[cfg(feature = "esp32")]
+use ssd1306::{
+    command::AddrMode,
+    mode::{BasicMode, BufferedGraphicsModeAsync},
+    prelude::I2CInterface,
+    size::DisplaySize128x64,
+    Ssd1306Async,
+};
+
+const WIDTH: usize = 128;
+const PAGES: usize = 8;
+const PAGE_HEIGHT: usize = 8;
+// Changed runs of a page closer than this many columns are sent as one: each
+// run costs 6 command bytes to address (column and page range, 3 bytes each)
+// plus the I2C framing of two more transfers, more than a few unchanged
+// columns
+const SPAN_GAP: usize = 8;
+
+/// SSD1306 128x64 over I2C, the display wired up on the board.
+#[cfg(feature = "esp32")]
+pub type Ssd1306Display<'a> = Ssd1306Async<
+    I2CInterface<I2c<'a, esp_hal::Async>>,
+    DisplaySize128x64,
+    BufferedGraphicsModeAsync<DisplaySize128x64>,
+>;
+
+/// The same display without a driver-side framebuffer, written to directly.
+#[cfg(feature = "esp32")]
+pub type RawSsd1306Display<'a> =
+    Ssd1306Async<I2CInterface<I2c<'a, esp_hal::Async>>, DisplaySize128x64, BasicMode>;
+
+/// A buffered display: drawing goes to an off-screen buffer and `flush`
+/// pushes it to the panel.
+///
+/// This is all the game needs on top of [`DrawTarget`], so any monochrome
+/// display (SH1106, SPI SSD1306, e-paper, a host framebuffer) can be used by
+/// implementing it.
+pub trait Flush: DrawTarget {
+    #[allow(async_fn_in_trait)]
+    async fn flush(&mut self) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "esp32")]
+impl Flush for Ssd1306Display<'_> {
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        Ssd1306Async::flush(self).await
+    }
+}
+
+/// 128x64 monochrome framebuffer in the SSD1306 memory layout: 8 pages of 8
+/// pixel rows, with a byte per column and page holding the top row in bit 0.
+///
+/// It remembers what the panel shows, so a flush only has to send the
+/// [`dirty_spans`](Self::dirty_spans).
+pub struct FrameBuffer {
+    pages: [[u8; WIDTH]; PAGES],
+    shown: [[u8; WIDTH]; PAGES],
+    // Until the first flush the panel shows whatever its RAM held
+    shown_valid: bool,
+}
+
+/// Columns `start..end` of a page that differ from what the panel shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub page: u8,
+    pub start: u8,
+    pub end: u8,
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameBuffer {
+    pub const fn new() -> Self {
+        Self {
+            pages: [[0; WIDTH]; PAGES],
+            shown: [[0; WIDTH]; PAGES],
+            shown_valid: false,
+        }
+    }
+
+    fn set_pixel(&mut self, point: Point, color: BinaryColor) {
+        let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y)) else {
+            return;
+        };
+        if x >= WIDTH || y >= PAGES * PAGE_HEIGHT {
+            return;
+        }
+
+        let byte = &mut self.pages[y / PAGE_HEIGHT][x];
+        let mask = 1 << (y % PAGE_HEIGHT);
+        if color.is_on() {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+
+    fn fill(&mut self, color: BinaryColor) {
+        let byte = if color.is_on() { 0xff } else { 0 };
+        self.pages = [[byte; WIDTH]; PAGES];
+    }
+
+    /// Runs of columns changed since the last
+    /// [`mark_flushed`](Self::mark_flushed), page by page. Runs separated by
+    /// a few unchanged columns are merged. Everything is dirty before the
+    /// first flush.
+    pub fn dirty_spans(&self) -> DirtySpans<'_> {
+        DirtySpans {
+            buffer: self,
+            page: 0,
+            column: 0,
+        }
+    }
+
+    /// Bytes to send for `span`, one per column.
+    pub fn span_data(&self, span: Span) -> &[u8] {
+        &self.pages[span.page as usize][span.start as usize..span.end as usize]
+    }
+
+    /// Records that the panel now shows the whole buffer.
+    pub fn mark_flushed(&mut self) {
+        self.shown = self.pages;
+        self.shown_valid = true;
+    }
+
+    fn is_dirty(&self, page: usize, column: usize) -> bool {
+        !self.shown_valid || self.pages[page][column] != self.shown[page][column]
+    }
+}
+
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, (PAGES * PAGE_HEIGHT) as u32)
+    }
+}
+
+impl DrawTarget for FrameBuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(point, color);
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(color);
+
+        Ok(())
+    }
+}
+
+/// Iterator returned by [`FrameBuffer::dirty_spans`].
+pub struct DirtySpans<'a> {
+    buffer: &'a FrameBuffer,
+    page: usize,
+    column: usize,
+}
+
+impl Iterator for DirtySpans<'_> {
+    type Item = Span;
+
+    fn next(&mut self) -> Option<Span> {
+        while self.page < PAGES {
+            let page = self.page;
+            let Some(start) = (self.column..WIDTH).find(|&c| self.buffer.is_dirty(page, c)) else {
+                self.page += 1;
+                self.column = 0;
+                continue;
+            };
+
+            // Grow the run until SPAN_GAP clean columns in a row
+            let mut end = start + 1;
+            let mut column = end;
+            while column < WIDTH && column - end < SPAN_GAP {
+                if self.buffer.is_dirty(page, column) {
+                    end = column + 1;
+                }
+                column += 1;
+            }
+            self.column = end;
+
+            return Some(Span {
+                page: page as u8,
+                start: start as u8,
+                end: end as u8,
+            });
+        }
+
+        None
+    }
+}
+
+/// The board's display, flushing only the columns of each page that changed
+/// since the last frame instead of the whole 1 KiB framebuffer.
+#[cfg(feature = "esp32")]
+pub struct PartialFlushDisplay<'a> {
+    display: RawSsd1306Display<'a>,
+    buffer: FrameBuffer,
+}
+
+#[cfg(feature = "esp32")]
+impl<'a> PartialFlushDisplay<'a> {
+    /// Initializes `display` for drawing through the framebuffer.
+    pub async fn new(
+        mut display: RawSsd1306Display<'a>,
+    ) -> Result<Self, <Ssd1306Display<'a> as DrawTarget>::Error> {
+        display.init_with_addr_mode(AddrMode::Horizontal).await?;
+
+        Ok(Self {
+            display,
+            buffer: FrameBuffer::new(),
+        })
+    }
+}
+
+#[cfg(feature = "esp32")]
+impl OriginDimensions for PartialFlushDisplay<'_> {
+    fn size(&self) -> Size {
+        self.buffer.size()
+    }
+}
+
+#[cfg(feature = "esp32")]
+impl<'a> DrawTarget for PartialFlushDisplay<'a> {
+    type Color = BinaryColor;
+    type Error = <Ssd1306Display<'a> as DrawTarget>::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.buffer.set_pixel(point, color);
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.buffer.fill(color);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "esp32")]
+impl Flush for PartialFlushDisplay<'_> {
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        for span in self.buffer.dirty_spans() {
+            let top = span.page * PAGE_HEIGHT as u8;
+            self.display
+                .set_draw_area((span.start, top), (span.end, top + PAGE_HEIGHT as u8))
+                .await?;
+            self.display.draw(self.buffer.span_data(span)).await?;
+        }
+        self.buffer.mark_flushed();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(buffer: &FrameBuffer) -> heapless::Vec<Span, 64> {
+        buffer.dirty_spans().collect()
+    }
+
+    fn flushed() -> FrameBuffer {
+        let mut buffer = FrameBuffer::new();
+        buffer.mark_flushed();
+        buffer
+    }
+
+    fn span(page: u8, start: u8, end: u8) -> Span {
+        Span { page, start, end }
+    }
+
+    #[test]
+    fn everything_is_dirty_before_the_first_flush() {
+        let buffer = FrameBuffer::new();
+
+        let expected: heapless::Vec<Span, 64> = (0..PAGES as u8)
+            .map(|page| span(page, 0, WIDTH as u8))
+            .collect();
+        assert_eq!(spans(&buffer), expected);
+    }
+
+    #[test]
+    fn nothing_is_dirty_after_a_flush() {
+        let mut buffer = FrameBuffer::new();
+        buffer.set_pixel(Point::new(3, 3), BinaryColor::On);
+        buffer.mark_flushed();
+
+        assert_eq!(spans(&buffer), []);
+    }
+
+    #[test]
+    fn changed_pixel_gives_one_span() {
+        let mut buffer = flushed();
+        buffer.set_pixel(Point::new(40, 20), BinaryColor::On);
+
+        assert_eq!(spans(&buffer), [span(2, 40, 41)]);
+        assert_eq!(buffer.span_data(span(2, 40, 41)), [0b0001_0000]);
+    }
+
+    #[test]
+    fn close_runs_merge() {
+        let mut buffer = flushed();
+        buffer.set_pixel(Point::new(10, 0), BinaryColor::On);
+        buffer.set_pixel(Point::new(10 + SPAN_GAP as i32, 0), BinaryColor::On);
+        buffer.set_pixel(Point::new(60, 0), BinaryColor::On);
+        buffer.set_pixel(Point::new(61 + SPAN_GAP as i32, 0), BinaryColor::On);
+
+        assert_eq!(
+            spans(&buffer),
+            [
+                span(0, 10, 11 + SPAN_GAP as u8),
+                span(0, 60, 61),
+                span(0, 61 + SPAN_GAP as u8, 62 + SPAN_GAP as u8),
+            ]
+        );
+    }
+
+    #[test]
+    fn last_column_is_included() {
+        let mut buffer = flushed();
+        buffer.set_pixel(Point::new(127, 63), BinaryColor::On);
+
+        assert_eq!(spans(&buffer), [span(7, 127, 128)]);
+    }
+}
@@ -1,26 +1,157 @@
+use core::fmt::Debug;
+
 use embedded_graphics::{
     image::Image,
     pixelcolor::BinaryColor,
     prelude::*,
-    primitives::{Circle, PrimitiveStyle},
+    primitives::{Circle, PrimitiveStyle, Rectangle},
 };
-use esp_hal::rng::Rng;
 use heapless::spsc::Queue;
 
-use crate::{game::DisplayType, sprites::RAW_PLANET_KILLER};
+use crate::{
+    collision::{sprite_hit, Hitbox, Mask},
+    level,
+    rng::GameRng,
+    sprites::{
+        ImgRawType, MASK_BOSS_CANNON, MASK_BOSS_CORE, MASK_FIGHTER, MASK_KAMIKAZE,
+        MASK_PLANET_KILLER, MASK_TURRET, RAW_BOSS_CANNON, RAW_BOSS_CORE, RAW_FIGHTER, RAW_KAMIKAZE,
+        RAW_PLANET_KILLER, RAW_TURRET,
+    },
+};
+
+pub const MAX_ENEMY_BULLETS: usize = 4;
+// Bosses fire volleys, past the limit of the level table
+const BOSS_MAX_BULLETS: usize = 10;
+pub const BULLET_QUEUE_SIZE: usize = BOSS_MAX_BULLETS + 1;
+// How far the fighter weaves back from where it spawned
+const FIGHTER_RANGE: i32 = 24;
+// A hovering kamikaze starts its dive with a chance of 1 in this per frame
+const KAMIKAZE_DIVE_CHANCE: u32 = 64;
+// The turret fires when the player is this close to its line of fire
+const TURRET_AIM_TOLERANCE: i32 = 4;
+// Minimum horizontal gap between turret bullets
+const TURRET_BULLET_GAP: i32 = 24;
+// Extra hit points per level
+const HP_PER_LEVEL: u32 = 2;
+const HEALTH_BAR_HEIGHT: u32 = 3;
+// Length of the destruction animation
+const EXPLOSION_FRAMES: u8 = 12;
+// The boss stays below its health bar, which spans the screen under the HUD
+const BOSS_TOP: i32 = 16;
+const BOSS_BAR_Y: i32 = 11;
+const BOSS_BAR_HEIGHT: u32 = 4;
+const BOSS_BAR_MARGIN: i32 = 4;
+// How far the cannons stick out in front of the core
+const BOSS_CANNON_REACH: i32 = 12;
+// Frames between the end of an attack and the next one, by phase
+const BOSS_SPREAD_PAUSE: u32 = 45;
+const BOSS_LASER_PAUSE: u32 = 30;
+const BOSS_FRENZY_PAUSE: u32 = 20;
+// The laser charges, showing where it will fire, then fires for a while
+const LASER_CHARGE_FRAMES: u8 = 24;
+const LASER_FIRE_FRAMES: u8 = 20;
+const LASER_WIDTH: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnemyKind {
+    /// Bounces up and down, firing at random.
+    PlanetKiller,
+    /// Zig-zags up and down and back and forth, firing small bullets.
+    Fighter,
+    /// Follows the player up and down and fires when lined up.
+    Turret,
+    /// Hovers, then dives at the player. Never fires, but ramming the player
+    /// costs a life.
+    Kamikaze,
+    /// Ends the boss levels. Fires spread shots, then sweeps a laser across
+    /// the screen as it takes damage, then does both, faster.
+    Boss,
+}
+
+/// Attacks of the boss, from full health down to its last third.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BossPhase {
+    Spread,
+    Laser,
+    Frenzy,
+}
 
-const MAX_ENEMY_BULLETS: usize = 4;
-pub const BULLET_QUEUE_SIZE: usize = MAX_ENEMY_BULLETS + 1;
-const INITIAL_BULLET_VELOCITY: i32 = -3;
-const INITIAL_ENEMY_VELOCITY: i32 = 1;
+/// The boss laser, fired from the nose of its core.
+#[derive(Clone, Copy, Debug)]
+struct Laser {
+    frame: u8,
+    // A sweep costs the player one life at most
+    landed: bool,
+}
+
+/// A bullet flying left, and up or down for the boss spread shots.
+#[derive(Clone, Copy, Debug)]
+pub struct EnemyBullet {
+    pub shape: Circle,
+    dy: i32,
+}
+
+impl Dimensions for EnemyBullet {
+    fn bounding_box(&self) -> Rectangle {
+        self.shape.bounding_box()
+    }
+}
+
+impl EnemyKind {
+    /// Hit points at level 1.
+    fn base_hp(self) -> u32 {
+        match self {
+            EnemyKind::PlanetKiller => 5,
+            EnemyKind::Fighter => 3,
+            EnemyKind::Turret => 6,
+            EnemyKind::Kamikaze => 2,
+            EnemyKind::Boss => 30,
+        }
+    }
+
+    fn sprite(self) -> &'static ImgRawType {
+        match self {
+            EnemyKind::PlanetKiller => &RAW_PLANET_KILLER,
+            EnemyKind::Fighter => &RAW_FIGHTER,
+            EnemyKind::Turret => &RAW_TURRET,
+            EnemyKind::Kamikaze => &RAW_KAMIKAZE,
+            EnemyKind::Boss => &RAW_BOSS_CORE,
+        }
+    }
+
+    fn mask(self) -> &'static Mask {
+        match self {
+            EnemyKind::PlanetKiller => &MASK_PLANET_KILLER,
+            EnemyKind::Fighter => &MASK_FIGHTER,
+            EnemyKind::Turret => &MASK_TURRET,
+            EnemyKind::Kamikaze => &MASK_KAMIKAZE,
+            EnemyKind::Boss => &MASK_BOSS_CORE,
+        }
+    }
+}
 
 pub struct Enemy {
-    pub img: Image<'static, super::sprites::ImgRawType>,
-    velocity: i32,
+    kind: EnemyKind,
+    // The whole sprite, or the core of the boss
+    pub img: Image<'static, ImgRawType>,
+    velocity: Point,
+    // Pixels per frame, see `LevelDef::enemy_speed`
+    speed: i32,
+    // Spawn position, the fighter weaves back from here and the kamikaze
+    // returns here after a dive
+    home: Point,
+    screen_width: i32,
     screen_height: i32,
-    rng: Rng,
+    diving: bool,
+    hp: u32,
+    max_hp: u32,
+    // Frames of the destruction animation played so far, once hp hit 0
+    exploding: Option<u8>,
+    // Boss attack in progress and frames until the next one
+    laser: Option<Laser>,
+    attack_in: u32,
     // Bullet data
-    pub bullets: Queue<Circle, BULLET_QUEUE_SIZE>,
+    pub bullets: Queue<EnemyBullet, BULLET_QUEUE_SIZE>,
     bullet_velocity: i32,
     // Current max bullet
     max_bullet: usize,
@@ -28,48 +159,211 @@ pub struct Enemy {
 }
 
 impl Enemy {
-    pub fn new(screen_width: i32, screen_height: i32, rng: Rng) -> Self {
-        let x = screen_width - (RAW_PLANET_KILLER.size().width * 15 / 10) as i32;
-        let y = screen_height / 2 - RAW_PLANET_KILLER.size().height as i32 / 2;
+    /// Spawns a `kind` enemy as strong as it gets at `level`, see
+    /// [`level::LEVELS`].
+    pub fn new(kind: EnemyKind, level: u32, screen_width: i32, screen_height: i32) -> Self {
+        let def = level::def(level);
+        let sprite = kind.sprite();
+        let x = screen_width - (sprite.size().width * 15 / 10) as i32;
+        let y = screen_height / 2 - sprite.size().height as i32 / 2;
+
+        let home = Point::new(x, y);
+        let img = Image::new(sprite, home);
 
-        let position = Point::new(x, y);
-        let img = Image::new(&super::sprites::RAW_PLANET_KILLER, position);
+        let speed = def.enemy_speed;
+        let max_hp = kind.base_hp() + level.saturating_sub(1) * HP_PER_LEVEL;
+        let velocity = match kind {
+            EnemyKind::PlanetKiller | EnemyKind::Kamikaze | EnemyKind::Boss => Point::new(0, speed),
+            EnemyKind::Fighter => Point::new(-1, speed),
+            EnemyKind::Turret => Point::zero(),
+        };
 
         Self {
+            kind,
             img,
-            rng,
-            velocity: INITIAL_ENEMY_VELOCITY,
+            velocity,
+            speed,
+            home,
+            screen_width,
             screen_height,
+            diving: false,
+            hp: max_hp,
+            max_hp,
+            exploding: None,
+            laser: None,
+            attack_in: BOSS_SPREAD_PAUSE,
             bullets: Queue::new(),
-            bullet_velocity: INITIAL_BULLET_VELOCITY,
-            max_bullet: 1,
+            bullet_velocity: -def.bullet_speed,
+            max_bullet: def.max_enemy_bullets,
             shoot_pending: false,
         }
     }
 
-    pub fn increase_level(&mut self) {
-        self.max_bullet = (self.max_bullet + 1).min(MAX_ENEMY_BULLETS);
-        self.velocity += if self.velocity < 0 { -1 } else { 1 };
+    pub fn kind(&self) -> EnemyKind {
+        self.kind
+    }
+
+    pub fn max_hp(&self) -> u32 {
+        self.max_hp
+    }
+
+    /// Area that rams the player: the sprite, or the boss with its cannons.
+    pub fn bounding_box(&self) -> Rectangle {
+        let bounding_box = self.img.bounding_box();
+        if self.kind != EnemyKind::Boss {
+            return bounding_box;
+        }
+
+        Rectangle::new(
+            bounding_box.top_left - Point::new(BOSS_CANNON_REACH, 0),
+            bounding_box.size + Size::new(BOSS_CANNON_REACH as u32, 0),
+        )
+    }
+
+    /// Whether `hitbox` touches the sprite, or the boss core or cannons. See
+    /// [`sprite_hit`].
+    pub fn is_hit_by(&self, hitbox: Hitbox) -> bool {
+        let top_left = self.img.bounding_box().top_left;
+        if sprite_hit(self.kind.mask(), top_left, hitbox) {
+            return true;
+        }
+
+        self.kind == EnemyKind::Boss
+            && self
+                .cannons()
+                .iter()
+                .any(|cannon| sprite_hit(&MASK_BOSS_CANNON, cannon.bounding_box().top_left, hitbox))
     }
 
-    pub fn update(&mut self) {
-        self.update_position();
+    /// The boss laser while it fires and until it hits the player.
+    pub fn laser(&self) -> Option<Rectangle> {
+        match self.laser {
+            Some(laser) if laser.frame >= LASER_CHARGE_FRAMES && !laser.landed => {
+                Some(self.laser_beam())
+            }
+            _ => None,
+        }
+    }
+
+    /// Stops the current laser sweep from hitting the player again.
+    pub fn laser_landed(&mut self) {
+        if let Some(laser) = self.laser.as_mut() {
+            laser.landed = true;
+        }
+    }
+
+    /// Whether it can still move, fire and be hit.
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    /// Whether the destruction animation is over and the next enemy can
+    /// take its place.
+    pub fn is_destroyed(&self) -> bool {
+        self.exploding
+            .is_some_and(|frame| frame >= EXPLOSION_FRAMES)
+    }
+
+    /// Takes a hit from a player bullet. Returns true if that destroyed it.
+    pub fn hit(&mut self) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+
+        self.hp -= 1;
+        if self.hp > 0 {
+            return false;
+        }
+
+        self.exploding = Some(0);
+        self.laser = None;
+        true
+    }
+
+    /// Carries on into the next level, `level`, with its settings.
+    pub fn level_up(&mut self, level: u32) {
+        let def = level::def(level);
+
+        self.max_hp += HP_PER_LEVEL;
+        self.hp += HP_PER_LEVEL;
+        self.max_bullet = def.max_enemy_bullets;
+        self.bullet_velocity = -def.bullet_speed;
+        self.speed = def.enemy_speed;
+        self.velocity.y = self.velocity.y.signum() * self.speed;
+    }
+
+    /// Moves and fires, aiming at the player's centre `target` where the
+    /// kind does.
+    pub fn update(&mut self, target: Point, rng: &mut impl GameRng) {
+        if let Some(frame) = self.exploding.as_mut() {
+            // Bullets already fired keep flying
+            *frame = frame.saturating_add(1);
+            self.update_bullet();
+            return;
+        }
+
+        self.update_position(target, rng);
         self.update_bullet();
-        if self.shoot_pending || self.rng.random() % 2 == 0 {
-            self.shoot();
+
+        match self.kind {
+            EnemyKind::Boss => self.boss_attack(rng),
+            EnemyKind::PlanetKiller | EnemyKind::Fighter => {
+                if self.shoot_pending || rng.next_u32() % 2 == 0 {
+                    self.shoot(rng);
+                }
+            }
+            EnemyKind::Turret => {
+                let aim = self.img.bounding_box().center().y;
+                if (aim - target.y).abs() <= TURRET_AIM_TOLERANCE {
+                    self.shoot(rng);
+                }
+            }
+            EnemyKind::Kamikaze => {}
+        }
+    }
+
+    pub fn update_position(&mut self, target: Point, rng: &mut impl GameRng) {
+        match self.kind {
+            EnemyKind::PlanetKiller => self.bounce(),
+            EnemyKind::Fighter => {
+                self.bounce();
+                self.weave();
+            }
+            EnemyKind::Turret => self.track(target.y),
+            EnemyKind::Kamikaze => self.dive(target, rng),
+            EnemyKind::Boss => {
+                // Harder to hit once cornered
+                self.bounce();
+                if self.boss_phase() == BossPhase::Frenzy {
+                    self.bounce();
+                }
+            }
         }
     }
 
-    pub fn update_position(&mut self) {
+    /// Sends the enemy back to where it spawned, after it rammed the player.
+    pub fn retreat(&mut self) {
+        let shift_by = self.home - self.img.bounding_box().top_left;
+        self.img = self.img.translate(shift_by);
+        self.diving = false;
+    }
+
+    // Up and down between the top and bottom of the screen
+    fn bounce(&mut self) {
         let y = self.img.bounding_box().top_left.y;
-        let mut new_y = y + self.velocity;
+        let mut new_y = y + self.velocity.y;
 
         let img_size = self.img.bounding_box().size;
+        let min_bound = if self.kind == EnemyKind::Boss {
+            BOSS_TOP
+        } else {
+            0
+        };
         let max_bound = self.screen_height - img_size.height as i32;
 
-        if new_y < 0 || new_y >= max_bound {
-            self.velocity = -self.velocity;
-            new_y = y + self.velocity;
+        if new_y < min_bound || new_y >= max_bound {
+            self.velocity.y = -self.velocity.y;
+            new_y = y + self.velocity.y;
         }
 
         let shift_by = Point::new(0, new_y - y);
@@ -77,7 +371,50 @@ impl Enemy {
         self.img = self.img.translate(shift_by);
     }
 
-    pub fn shoot(&mut self) {
+    // Back and forth up to `FIGHTER_RANGE` in front of the spawn position
+    fn weave(&mut self) {
+        let x = self.img.bounding_box().top_left.x;
+        let new_x = x + self.velocity.x;
+
+        if new_x < self.home.x - FIGHTER_RANGE || new_x > self.home.x {
+            self.velocity.x = -self.velocity.x;
+        }
+
+        self.img = self.img.translate(Point::new(self.velocity.x, 0));
+    }
+
+    // Towards the target height, one pixel per frame so the player can
+    // outrun it
+    fn track(&mut self, target_y: i32) {
+        let bounding_box = self.img.bounding_box();
+        let max_y = self.screen_height - bounding_box.size.height as i32;
+
+        let dy = (target_y - bounding_box.center().y).signum();
+        let new_y = (bounding_box.top_left.y + dy).clamp(0, max_y);
+
+        let shift_by = Point::new(0, new_y - bounding_box.top_left.y);
+        self.img = self.img.translate(shift_by);
+    }
+
+    // Bounces until it dives, then flies left steering towards the target
+    // and comes back home once off screen
+    fn dive(&mut self, target: Point, rng: &mut impl GameRng) {
+        if !self.diving {
+            self.bounce();
+            self.diving = rng.next_u32() % KAMIKAZE_DIVE_CHANCE == 0;
+            return;
+        }
+
+        let bounding_box = self.img.bounding_box();
+        let dy = (target.y - bounding_box.center().y).signum();
+        self.img = self.img.translate(Point::new(-(self.speed + 1), dy));
+
+        if bounding_box.bottom_right().map_or(0, |p| p.x) < 0 {
+            self.retreat();
+        }
+    }
+
+    pub fn shoot(&mut self, rng: &mut impl GameRng) {
         if self.bullets.is_full() || self.bullets.len() >= self.max_bullet {
             return;
         }
@@ -85,17 +422,26 @@ impl Enemy {
         let bounding_box = self.img.bounding_box();
 
         if let Some(last_bullet) = self.bullets.iter().last() {
-            // Check if the new bullet's position is too close to the last bullet's position
-            if (bounding_box.top_left.x - last_bullet.top_left.x).abs() < 10
-                || (bounding_box.top_left.y - last_bullet.top_left.y).abs() < 20
-            {
-                self.shoot_pending = true;
+            let dx = (bounding_box.top_left.x - last_bullet.shape.top_left.x).abs();
+            let dy = (bounding_box.top_left.y - last_bullet.shape.top_left.y).abs();
+
+            // The turret fires along the same line every time, so only keep
+            // its bullets apart horizontally
+            let too_close = match self.kind {
+                EnemyKind::Turret => dx < TURRET_BULLET_GAP,
+                // Check if the new bullet's position is too close to the last bullet's position
+                _ => dx < 10 || dy < 20,
+            };
+            if too_close {
+                self.shoot_pending = self.kind != EnemyKind::Turret;
                 return;
             }
         }
 
-        let rand_num = self.rng.random() % 5;
-        let bullet_size = 5 + rand_num;
+        let bullet_size = match self.kind {
+            EnemyKind::Fighter => 3,
+            _ => 5 + rng.next_u32() % 5,
+        };
 
         let enemy_pos = self.img.bounding_box().top_left;
         let enemy_size = bounding_box.size;
@@ -104,21 +450,134 @@ impl Enemy {
             enemy_pos.x - enemy_size.width as i32,
             enemy_pos.y + enemy_size.height as i32 / 2,
         );
-        let bullet = Circle::new(pos, bullet_size);
+        let bullet = EnemyBullet {
+            shape: Circle::new(pos, bullet_size),
+            dy: 0,
+        };
 
         self.bullets.enqueue(bullet).unwrap();
     }
 
+    fn boss_phase(&self) -> BossPhase {
+        if self.hp * 3 > self.max_hp * 2 {
+            BossPhase::Spread
+        } else if self.hp * 3 > self.max_hp {
+            BossPhase::Laser
+        } else {
+            BossPhase::Frenzy
+        }
+    }
+
+    // One attack at a time, with a pause after each
+    fn boss_attack(&mut self, rng: &mut impl GameRng) {
+        if let Some(laser) = self.laser.as_mut() {
+            laser.frame += 1;
+            if laser.frame >= LASER_CHARGE_FRAMES + LASER_FIRE_FRAMES {
+                self.laser = None;
+            }
+            return;
+        }
+
+        if self.attack_in > 0 {
+            self.attack_in -= 1;
+            return;
+        }
+
+        match self.boss_phase() {
+            BossPhase::Spread => {
+                self.spread(3);
+                self.attack_in = BOSS_SPREAD_PAUSE;
+            }
+            BossPhase::Laser => {
+                self.fire_laser();
+                self.attack_in = BOSS_LASER_PAUSE;
+            }
+            BossPhase::Frenzy => {
+                if rng.next_u32() % 2 == 0 {
+                    self.spread(5);
+                } else {
+                    self.fire_laser();
+                }
+                self.attack_in = BOSS_FRENZY_PAUSE;
+            }
+        }
+    }
+
+    fn fire_laser(&mut self) {
+        self.laser = Some(Laser {
+            frame: 0,
+            landed: false,
+        });
+    }
+
+    // A fan of `count` bullets, the ones going up from the upper cannon, the
+    // ones going down from the lower one
+    fn spread(&mut self, count: i32) {
+        if self.bullets.len() + count as usize > BOSS_MAX_BULLETS {
+            return;
+        }
+
+        let [upper, lower] = self.cannons().map(|cannon| {
+            let bounding_box = cannon.bounding_box();
+            Point::new(bounding_box.top_left.x, bounding_box.center().y)
+        });
+        let nose = self.laser_origin();
+
+        for dy in -count / 2..=count / 2 {
+            let muzzle = match dy.signum() {
+                -1 => upper,
+                0 => nose,
+                _ => lower,
+            };
+            let bullet = EnemyBullet {
+                shape: Circle::with_center(muzzle, 5),
+                dy,
+            };
+            self.bullets.enqueue(bullet).unwrap();
+        }
+    }
+
+    fn cannons(&self) -> [Image<'static, ImgRawType>; 2] {
+        let bounding_box = self.img.bounding_box();
+        let cannon_height = RAW_BOSS_CANNON.size().height as i32;
+        let x = bounding_box.top_left.x - BOSS_CANNON_REACH;
+        let upper_y = bounding_box.top_left.y + 1;
+        let lower_y = bounding_box.top_left.y + bounding_box.size.height as i32 - 1 - cannon_height;
+
+        [
+            Image::new(&RAW_BOSS_CANNON, Point::new(x, upper_y)),
+            Image::new(&RAW_BOSS_CANNON, Point::new(x, lower_y)),
+        ]
+    }
+
+    fn laser_origin(&self) -> Point {
+        let bounding_box = self.img.bounding_box();
+        Point::new(bounding_box.top_left.x, bounding_box.center().y)
+    }
+
+    // From the nose of the core to the left edge of the screen, following
+    // the boss up and down
+    fn laser_beam(&self) -> Rectangle {
+        let origin = self.laser_origin();
+        let top_left = Point::new(0, origin.y - LASER_WIDTH as i32 / 2);
+        Rectangle::new(top_left, Size::new(origin.x as u32, LASER_WIDTH))
+    }
+
     fn update_bullet(&mut self) {
         if self.bullets.is_empty() {
             return;
         }
 
-        let mut new_queue = Queue::<Circle, BULLET_QUEUE_SIZE>::new();
+        let mut new_queue = Queue::<EnemyBullet, BULLET_QUEUE_SIZE>::new();
 
-        while let Some(bullet) = self.bullets.dequeue() {
-            let bullet = bullet.translate(Point::new(self.bullet_velocity, 0));
-            if bullet.top_left.x <= 0 {
+        while let Some(mut bullet) = self.bullets.dequeue() {
+            bullet.shape = bullet
+                .shape
+                .translate(Point::new(self.bullet_velocity, bullet.dy));
+            let bounding_box = bullet.shape.bounding_box();
+            let off_screen = bounding_box.top_left.y >= self.screen_height
+                || bounding_box.top_left.y + (bounding_box.size.height as i32) <= 0;
+            if bounding_box.top_left.x <= 0 || off_screen {
                 continue;
             }
             new_queue.enqueue(bullet).unwrap()
@@ -126,14 +585,121 @@ impl Enemy {
         self.bullets = new_queue;
     }
 
-    pub fn draw(&self, display: &mut DisplayType) {
-        self.img.draw(display).unwrap();
+    pub fn draw<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        if let Some(frame) = self.exploding {
+            self.draw_explosion(display, frame);
+        } else {
+            self.img.draw(display).unwrap();
+            if self.kind == EnemyKind::Boss {
+                for cannon in self.cannons() {
+                    cannon.draw(display).unwrap();
+                }
+                self.draw_laser(display);
+            }
+            self.draw_health_bar(display);
+        }
         self.draw_bullet(display);
     }
 
-    pub fn draw_bullet(&self, display: &mut DisplayType) {
+    /// Draws the remaining hit points as a bar above the sprite, or below it
+    /// when the sprite is at the top of the screen. The boss gets a bar
+    /// across the whole screen.
+    fn draw_health_bar<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let bar = if self.kind == EnemyKind::Boss {
+            let width = self.screen_width - 2 * BOSS_BAR_MARGIN;
+            Rectangle::new(
+                Point::new(BOSS_BAR_MARGIN, BOSS_BAR_Y),
+                Size::new(width.max(0) as u32, BOSS_BAR_HEIGHT),
+            )
+        } else {
+            let bounding_box = self.img.bounding_box();
+            let gap = HEALTH_BAR_HEIGHT as i32 + 1;
+            let y = if bounding_box.top_left.y >= gap {
+                bounding_box.top_left.y - gap
+            } else {
+                bounding_box.top_left.y + bounding_box.size.height as i32 + 1
+            };
+            Rectangle::new(
+                Point::new(bounding_box.top_left.x, y),
+                Size::new(bounding_box.size.width, HEALTH_BAR_HEIGHT),
+            )
+        };
+
+        bar.into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(display)
+            .unwrap();
+
+        let filled = bar.size.width * self.hp / self.max_hp.max(1);
+        Rectangle::new(bar.top_left, Size::new(filled, bar.size.height))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(display)
+            .unwrap();
+    }
+
+    /// A ring growing out of the wreck with debris flying off diagonally.
+    fn draw_explosion<D>(&self, display: &mut D, frame: u8)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let center = self.img.bounding_box().center();
+        let frame = frame as i32;
+
+        Circle::with_center(center, 4 + frame as u32 * 3)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(display)
+            .unwrap();
+
+        for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let offset = Point::new(dx, dy) * (4 + frame * 2);
+            Rectangle::with_center(center + offset, Size::new(2, 2))
+                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+                .draw(display)
+                .unwrap();
+        }
+    }
+
+    /// A dotted line while the laser charges, then the beam itself.
+    fn draw_laser<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let Some(laser) = self.laser else {
+            return;
+        };
+
+        if laser.frame < LASER_CHARGE_FRAMES {
+            let origin = self.laser_origin();
+            let dots = (0..origin.x)
+                .step_by(4)
+                .map(|x| Pixel(Point::new(x, origin.y), BinaryColor::On));
+            display.draw_iter(dots).unwrap();
+            return;
+        }
+
+        self.laser_beam()
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(display)
+            .unwrap();
+    }
+
+    pub fn draw_bullet<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
         self.bullets.iter().for_each(|bullet| {
             bullet
+                .shape
                 .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
                 .draw(display)
                 .unwrap();
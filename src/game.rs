@@ -1,402 +1,328 @@
-use core::fmt::Write;
-use core::sync::atomic::{AtomicBool, Ordering};
-use embassy_time::{Duration, Timer};
-use embedded_graphics::image::Image;
-use embedded_graphics::mono_font::ascii::FONT_9X18_BOLD;
-use embedded_graphics::primitives::{Circle, PrimitiveStyle};
-use embedded_graphics::{
-    mono_font::{ascii::FONT_6X10, MonoTextStyleBuilder},
-    pixelcolor::BinaryColor,
-    prelude::*,
-    primitives::Rectangle,
-    text::{Baseline, Text},
+use core::fmt::Debug;
+use core::sync::atomic::Ordering;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+#[cfg(feature = "buzzer")]
+use crate::audio::melody;
+use crate::audio::{self, music};
+use crate::display::Flush;
+use crate::highscore::{Entry, HighScores, InitialsEntry, ScoreStorage, INITIALS_LEN};
+use crate::input::{InputEventKind, INPUT_EVENTS};
+use crate::player::{PLAYER_X_AXIS, PLAYER_Y_AXIS};
+use crate::replay::{quantize_axis, Recorder, Replay};
+use crate::rng::XorShift32;
+#[cfg(feature = "debug")]
+use crate::screen::FrameStatsOverlay;
+use crate::screen::{
+    GameOverScreen, HighScoresScreen, InitialsScreen, LevelCompletedScreen, PauseScreen,
+    PlayScreen, Screen, TitleScreen,
 };
-use esp_hal::{i2c::master::I2c, rng::Rng};
-use heapless::spsc::Queue;
-use heapless::String;
-use ssd1306::{
-    mode::BufferedGraphicsModeAsync, prelude::I2CInterface, size::DisplaySize128x64, Ssd1306Async,
-};
-
-use crate::audio::{music, AudioEffect};
-use crate::sprites::{self};
-use crate::{enemy::Enemy, player::Player};
-
-pub type DisplayType<'a> = Ssd1306Async<
-    I2CInterface<I2c<'a, esp_hal::Async>>,
-    DisplaySize128x64,
-    BufferedGraphicsModeAsync<DisplaySize128x64>,
->;
-
-pub static BUTTON_PRESSED: AtomicBool = AtomicBool::new(false);
-
-const LEVEL_INTERVAL: u32 = 50;
-
-#[derive(PartialEq, Clone, Copy)]
-pub enum GameState {
-    Menu,
-    Playing,
-    LevelCompleted,
-    Dead,
+use crate::timing::{FrameClock, FrameStats};
+use crate::world::{Event, Events, Input, World};
+
+// Offsets of the playfield, one per frame, when the player loses a life
+const SHAKE_OFFSETS: [Point; 6] = [
+    Point::new(2, 0),
+    Point::new(-2, 1),
+    Point::new(1, -2),
+    Point::new(-1, 1),
+    Point::new(1, 0),
+    Point::new(0, -1),
+];
+
+pub use crate::world::GameState;
+
+pub struct Game<'a, D> {
+    world: World,
+    display: D,
+    recorder: Option<&'a mut Recorder>,
+    save_recording: fn(&Recorder),
+    replay: Option<Replay<'a>>,
+    high_scores: HighScores,
+    score_storage: Option<&'a mut dyn ScoreStorage>,
+    // Set while the player types their initials after a high score
+    initials: Option<InitialsEntry>,
+    // Rank of the high score made by the last run
+    new_rank: Option<usize>,
+    // Frames left of the screen shake
+    shake_frames: usize,
+    // Input events from before this are stale and ignored
+    input_since: Instant,
+    // Whether the main button is down, from its press and release events
+    fire_held: bool,
+    frame_stats: FrameStats,
 }
 
-pub struct Game<'a> {
-    state: GameState,
-    score: u32,
-    player: Player,
-    enemy: Enemy,
-    display: DisplayType<'a>,
-    rng: Rng,
-    level: u32,
-    audio: AudioEffect<'a>,
-}
-
-impl<'a> Game<'a> {
-    pub fn new(display: DisplayType<'a>, rng: Rng, audio: AudioEffect<'a>) -> Self {
-        let (player, enemy) = Game::init_game_state(&display, rng);
+impl<'a, D> Game<'a, D>
+where
+    D: Flush<Color = BinaryColor>,
+    D::Error: Debug,
+{
+    pub fn new(display: D, rng: XorShift32) -> Self {
+        let screen_size = display.bounding_box().size;
+        let world = World::new(screen_size.width as i32, screen_size.height as i32, rng);
         Self {
-            audio,
-            state: GameState::Menu,
-            score: 0,
-            player,
-            enemy,
+            world,
             display,
-            rng,
-            level: 1,
+            recorder: None,
+            save_recording: |_| {},
+            replay: None,
+            high_scores: HighScores::new(),
+            score_storage: None,
+            initials: None,
+            new_rank: None,
+            shake_frames: 0,
+            input_since: Instant::MIN,
+            fire_held: false,
+            frame_stats: FrameStats::new(),
         }
     }
 
-    fn init_game_state(display: &DisplayType<'a>, rng: Rng) -> (Player, Enemy) {
-        (Game::spawn_player(display), Game::init_enemy(display, rng))
+    /// Records the input of every run into `recorder` and passes it to `save`
+    /// once the player dies.
+    pub fn record(mut self, recorder: &'a mut Recorder, save: fn(&Recorder)) -> Self {
+        self.recorder = Some(recorder);
+        self.save_recording = save;
+        self
     }
 
-    fn reset_game(&mut self) {
-        self.score = 0;
-        self.level = 1;
-        let (player, enemy) = Game::init_game_state(&self.display, self.rng);
-        self.player = player;
-        self.enemy = enemy;
+    /// Plays back `replay` instead of reading the controls until it runs
+    /// out. The game must have been created with the seed of the replay.
+    pub fn replay(mut self, replay: Replay<'a>) -> Self {
+        self.replay = Some(replay);
+        self
     }
 
-    fn init_enemy(display: &DisplayType, rng: Rng) -> Enemy {
-        let screen_dims = display.dimensions();
-        Enemy::new(screen_dims.0 as i32, screen_dims.1 as i32, rng)
+    /// Loads the high-score table from `storage` and saves it back whenever
+    /// a run makes it in. Without storage the table only lasts until reset.
+    pub fn high_scores(mut self, storage: &'a mut dyn ScoreStorage) -> Self {
+        self.high_scores = storage.load();
+        self.score_storage = Some(storage);
+        self
     }
 
     pub async fn start(&mut self) {
         self.clear_display();
-        let mut title_buff: String<64> = String::new();
+        #[cfg(feature = "buzzer")]
+        audio::play_melody(&melody::TITLE_THEME);
 
-        let mut prev_state;
+        let mut clock = FrameClock::new();
 
         loop {
-            title_buff.clear();
-
-            prev_state = self.state;
-
-            match self.state {
-                GameState::Menu => {
-                    if BUTTON_PRESSED.swap(false, Ordering::Relaxed) {
-                        self.reset_game();
-                        self.state = GameState::Playing;
-                    }
-                }
-                GameState::Playing => {
-                    self.level_handle();
-                    if BUTTON_PRESSED.swap(false, Ordering::Relaxed) && self.player.shoot() {
-                        self.audio.play_tone(music::NOTE_D6, 20);
-                    }
-                    self.enemy.update();
-                    self.player.update();
-                    self.enemy_collison();
-                    self.player_collison();
-                    self.bullets_collison();
-                    self.draw_game();
-
-                    self.display.flush().await.unwrap();
-                }
-                _ => {
-                    if BUTTON_PRESSED.swap(false, Ordering::Relaxed) {
-                        self.state = GameState::Menu;
-                    }
+            let frame_start = Instant::now();
+            let prev_state = self.world.state();
+
+            // Run the updates due, catching up after a slow frame, but draw
+            // any change of state before running the rest
+            let due = clock.due_updates();
+            for done in 1..=due {
+                self.update();
+                if self.world.state() != prev_state {
+                    clock.defer(due - done);
+                    break;
                 }
             }
 
-            self.clear_display();
-
-            match self.state {
-                GameState::Menu => self.draw_welcome_screen(),
-                GameState::Playing => self.draw_game(),
-                GameState::Dead => {
-                    self.draw_game_over();
-                }
-                GameState::LevelCompleted => {}
-            }
-
+            self.render();
             self.display.flush().await.unwrap();
+            self.frame_stats.record(frame_start);
 
-            if prev_state == GameState::Playing && self.state == GameState::Dead {
-                BUTTON_PRESSED.store(false, Ordering::Relaxed);
-                // Wait and show the game over screen
+            if prev_state == GameState::Playing && self.world.state() == GameState::Dead {
+                // Wait and show the game over screen, ignoring any press made
+                // in the heat of the game
                 Timer::after(Duration::from_millis(500)).await;
-                BUTTON_PRESSED.store(false, Ordering::Relaxed);
-            }
+                self.input_since = Instant::now();
 
-            Timer::after(Duration::from_millis(15)).await;
-        }
-    }
+                self.new_rank = None;
+                if self.high_scores.qualifies(self.world.score()) {
+                    self.initials = Some(InitialsEntry::new());
+                }
+                clock.restart();
+            }
 
-    fn level_handle(&mut self) {
-        let new_level = self.score / LEVEL_INTERVAL + 1;
-        if new_level > self.level {
-            self.level = new_level;
-            self.player.increase_level();
-            self.enemy.increase_level();
+            clock.wait().await;
         }
     }
 
-    fn spawn_player(display: &DisplayType) -> Player {
-        let screen_dims = display.dimensions();
-        Player::new(screen_dims.0 as i32, screen_dims.1 as i32)
-    }
-
-    fn clear_display(&mut self) {
-        self.display.clear_buffer();
-        self.display.clear(BinaryColor::Off).unwrap();
-    }
-
-    fn draw_game(&mut self) {
-        self.player.draw(&mut self.display);
-        self.draw_enemy();
-        self.print_score();
-        self.print_level();
-        self.print_lives();
-        self.draw_universe();
-    }
-
-    fn draw_game_over(&mut self) {
-        let mut score_text: String<32> = String::new();
-
-        Image::new(&sprites::RAW_GAME_OVER, Point::new(16, 28))
-            .draw(&mut self.display)
-            .unwrap();
-
-        write!(score_text, "Score: {}", self.score).unwrap();
-        let text_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X10)
-            .text_color(BinaryColor::On)
-            .build();
-
-        let text_width = score_text.len() as i32 * FONT_6X10.character_size.width as i32;
-
-        // // Get display dimensions
-        let (width, _) = self.display.dimensions();
-
-        // // Calculate top-left position to center the text
-        let x = (width as i32 - text_width) / 2;
-        // let y = (height as i32 - text_height) / 2;
-
-        Text::with_baseline(&score_text, Point::new(x, 42), text_style, Baseline::Top)
-            .draw(&mut self.display)
-            .unwrap();
-    }
-
-    fn draw_welcome_screen(&mut self) {
-        Image::new(&sprites::RAW_BOW_ARROW, Point::new(16, 0))
-            .draw(&mut self.display)
-            .unwrap();
-
-        let tile1 = "COSMIC";
-        let title2 = "YUDH";
-
-        let text_style = MonoTextStyleBuilder::new()
-            .font(&FONT_9X18_BOLD)
-            .text_color(BinaryColor::On)
-            .build();
-
-        let x = sprites::RAW_BOW_ARROW.size().width as i32 + 30;
-        Text::with_baseline(tile1, Point::new(x, 15), text_style, Baseline::Top)
-            .draw(&mut self.display)
-            .unwrap();
+    /// Advances the game by one [`FRAME_TIME`](crate::timing::FRAME_TIME):
+    /// reads the controls, steps the world (or the initials entry) and plays
+    /// the sounds of what happened.
+    fn update(&mut self) {
+        let prev_state = self.world.state();
 
-        Text::with_baseline(title2, Point::new(x + 3, 35), text_style, Baseline::Top)
-            .draw(&mut self.display)
-            .unwrap();
-
-        self.draw_universe();
-    }
-
-    fn draw_universe(&mut self) {
-        let stars = [
-            // Star size : 1
-            (10, 10, 1),
-            (30, 5, 1),
-            (50, 25, 1),
-            (80, 10, 1),
-            (100, 30, 1),
-            (110, 5, 1),
-            (60, 40, 1),
-            (15, 25, 1),
-            (25, 35, 1),
-            (35, 45, 1),
-            (55, 60, 1),
-            (65, 20, 1),
-            (75, 30, 1),
-            (85, 40, 1),
-            (105, 60, 1),
-            (115, 15, 1),
-            // Star size: 2
-            (5, 50, 2),
-            (20, 15, 2),
-            (25, 58, 2),
-            (90, 20, 2),
-            (95, 50, 2),
-            (123, 25, 2),
-        ];
-
-        for (x, y, size) in stars {
-            Circle::new(Point::new(x, y), size)
-                .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
-                .draw(&mut self.display)
-                .unwrap();
+        let input = self.read_input();
+        let events = if let Some(entry) = self.initials.as_mut() {
+            if let Some(initials) = entry.step(input) {
+                self.add_high_score(initials);
+            }
+            Events::new()
+        } else {
+            self.world.step(input)
+        };
+        self.record_input(prev_state, input);
+        self.play_sounds(&events);
+        self.update_shake(&events);
+
+        #[cfg(feature = "buzzer")]
+        if prev_state != GameState::Menu && self.world.state() == GameState::Menu {
+            audio::play_melody(&melody::TITLE_THEME);
         }
     }
 
-    fn print_score(&mut self) {
-        let mut score_text: String<16> = String::new();
-        write!(score_text, "Score: {}", self.score).unwrap();
-
-        let text_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X10)
-            .text_color(BinaryColor::On)
-            .build();
-
-        let text_width = score_text.len() as i32 * FONT_6X10.character_size.width as i32;
-        let text_height = FONT_6X10.character_size.height as i32;
-
-        let (width, height) = self.display.dimensions();
-
-        // Calculate top-left position to center the text
-        let x = (width as i32 - text_width) / 2;
-        let y = height as i32 - text_height;
-
-        Text::with_baseline(&score_text, Point::new(x, y), text_style, Baseline::Top)
-            .draw(&mut self.display)
-            .unwrap();
-    }
-
-    fn print_level(&mut self) {
-        let mut score_text: String<16> = String::new();
-        write!(score_text, "L: {}", self.level).unwrap();
-
-        let text_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X10)
-            .text_color(BinaryColor::On)
-            .build();
-
-        let x = 60;
-        let y = 0;
-
-        Text::with_baseline(&score_text, Point::new(x, y), text_style, Baseline::Top)
-            .draw(&mut self.display)
-            .unwrap();
-    }
-
-    fn print_lives(&mut self) {
-        let img_width: i32 = sprites::RAW_HEART.bounding_box().size.width as i32;
-
-        let x = 28;
+    fn read_input(&mut self) -> Input {
+        if let Some(input) = self.replay.as_mut().and_then(Iterator::next) {
+            return input;
+        }
 
-        for i in 0..self.player.lives {
-            let image = Image::new(
-                &sprites::RAW_HEART,
-                Point::new(x + (i as i32 * img_width), 0),
-            );
-            image.draw(&mut self.display).unwrap();
+        let mut input = Input {
+            // Rounded as recorded, so replays see the same input
+            x_axis: quantize_axis(PLAYER_X_AXIS.load(Ordering::Relaxed)),
+            y_axis: quantize_axis(PLAYER_Y_AXIS.load(Ordering::Relaxed)),
+            ..Input::default()
+        };
+
+        while let Ok(event) = INPUT_EVENTS.try_receive() {
+            // Stale events still tell whether the button is down
+            match event.kind {
+                InputEventKind::Pressed => self.fire_held = true,
+                InputEventKind::Released => self.fire_held = false,
+                _ => {}
+            }
+            if event.at < self.input_since {
+                continue;
+            }
+            match event.kind {
+                InputEventKind::Pressed => input.fire = true,
+                InputEventKind::Pause => input.pause = true,
+                InputEventKind::Released
+                | InputEventKind::LongPress
+                | InputEventKind::DoubleTap
+                | InputEventKind::Direction(_) => {}
+            }
         }
-    }
+        input.fire_held = self.fire_held;
 
-    fn draw_enemy(&mut self) {
-        self.enemy.draw(&mut self.display);
+        input
     }
 
-    fn enemy_collison(&mut self) {
-        let enemy_bb = self.enemy.img.bounding_box();
+    fn record_input(&mut self, prev_state: GameState, input: Input) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
 
-        let mut new_queue = Queue::new();
-
-        while let Some(bullet) = self.player.bullets.dequeue() {
-            if detect_collison(bullet, enemy_bb) {
-                self.score += 1;
-                self.audio.play_tone(music::NOTE_B4, 20);
-            } else {
-                new_queue.enqueue(bullet).unwrap();
+        match (prev_state, self.world.state()) {
+            (GameState::Menu, GameState::Playing) => {
+                recorder.start(self.world.seed());
+                recorder.record(input);
+            }
+            // The run ended, by dying or quitting from the pause menu
+            (GameState::Playing, GameState::Dead) | (GameState::Paused, GameState::Menu) => {
+                recorder.record(input);
+                (self.save_recording)(recorder);
+            }
+            (GameState::Playing | GameState::Paused | GameState::LevelCompleted, _) => {
+                recorder.record(input)
             }
+            _ => {}
         }
-
-        self.player.bullets = new_queue;
     }
 
-    fn player_collison(&mut self) {
-        let player_bb = self.player.img.bounding_box();
+    fn add_high_score(&mut self, initials: [u8; INITIALS_LEN]) {
+        self.initials = None;
+        self.new_rank = self.high_scores.insert(Entry {
+            initials,
+            score: self.world.score(),
+            level: self.world.level(),
+        });
 
-        let mut new_queue = Queue::new();
+        if let Some(storage) = self.score_storage.as_mut() {
+            storage.save(&self.high_scores);
+        }
+    }
 
-        while let Some(bullet) = self.enemy.bullets.dequeue() {
-            if detect_collison(bullet.bounding_box(), player_bb) {
-                self.player.lives = self.player.lives.saturating_sub(1);
-                self.audio.play_tone(music::NOTE_FS2, 20);
-            } else {
-                new_queue.enqueue(bullet).unwrap();
+    fn play_sounds(&self, events: &Events) {
+        for event in events {
+            match event {
+                Event::Shot => audio::play_tone(music::NOTE_D6, 20),
+                Event::EnemyHit => audio::play_tone(music::NOTE_B4, 20),
+                Event::EnemyDestroyed => audio::play_tone(music::NOTE_C3, 120),
+                Event::BossDefeated => audio::play_tone(music::NOTE_C2, 400),
+                Event::PlayerHit => audio::play_tone(music::NOTE_FS2, 20),
+                Event::ShieldHit => audio::play_tone(music::NOTE_G5, 40),
+                Event::PowerUp(_) => audio::play_tone(music::NOTE_E6, 80),
+                Event::BulletsCollided => audio::play_tone(music::NOTE_AS6, 20),
+                #[cfg(feature = "buzzer")]
+                Event::LevelCompleted(_) => audio::play_melody(&melody::LEVEL_COMPLETE),
+                #[cfg(feature = "buzzer")]
+                Event::Died => audio::play_melody(&melody::GAME_OVER),
+                // A single note stands in for the jingle without the melodies
+                #[cfg(not(feature = "buzzer"))]
+                Event::LevelCompleted(_) => audio::play_tone(music::NOTE_C6, 200),
+                #[cfg(not(feature = "buzzer"))]
+                Event::Died => {}
+                Event::LevelUp(_) => {}
+                Event::ToggleSound => audio::set_enabled(!audio::is_enabled()),
             }
         }
+    }
 
-        self.enemy.bullets = new_queue;
-
-        if self.player.lives == 0 {
-            self.state = GameState::Dead;
-            // Clear any accident click during the game play
-            BUTTON_PRESSED.store(false, Ordering::Relaxed);
+    /// Starts shaking the playfield when the player loses a life, or moves
+    /// on to the next offset of the shake.
+    fn update_shake(&mut self, events: &Events) {
+        if events.contains(&Event::PlayerHit) {
+            self.shake_frames = SHAKE_OFFSETS.len();
+        } else {
+            self.shake_frames = self.shake_frames.saturating_sub(1);
         }
     }
 
-    fn bullets_collison(&mut self) {
-        let mut new_player_bullets = Queue::new();
-        let mut new_enemy_bullets = self.enemy.bullets.clone();
+    fn clear_display(&mut self) {
+        self.display.clear(BinaryColor::Off).unwrap();
+    }
 
-        // Collect bullets that survived collision check
-        while let Some(player_bullet) = self.player.bullets.dequeue() {
-            let mut collided = false;
+    /// Draws the screen of the current state, from scratch.
+    fn render(&mut self) {
+        self.clear_display();
 
-            let mut tmp_enemy_bullets = Queue::new();
-            // Check for collisions with any enemy bullet
-            while let Some(enemy_bullet) = new_enemy_bullets.dequeue() {
-                if detect_collison(player_bullet.bounding_box(), enemy_bullet.bounding_box()) {
-                    collided = true;
-                    self.audio.play_tone(music::NOTE_AS6, 20);
+        let offset = match self.shake_frames {
+            0 => Point::zero(),
+            frames => SHAKE_OFFSETS[SHAKE_OFFSETS.len() - frames],
+        };
+        let play = PlayScreen {
+            world: &self.world,
+            offset,
+        };
+        let display = &mut self.display;
+
+        match self.world.state() {
+            GameState::Menu => TitleScreen.render(display),
+            GameState::Playing => play.render(display),
+            GameState::Paused => PauseScreen { game: play }.render(display),
+            GameState::Dead => {
+                let score = self.world.score();
+                if let Some(entry) = self.initials.as_ref() {
+                    InitialsScreen { entry, score }.render(display);
+                } else if let Some(new_rank) = self.new_rank {
+                    let high_scores = &self.high_scores;
+                    HighScoresScreen {
+                        high_scores,
+                        new_rank,
+                    }
+                    .render(display);
                 } else {
-                    tmp_enemy_bullets.enqueue(enemy_bullet).unwrap();
+                    GameOverScreen { score }.render(display);
                 }
             }
-
-            new_enemy_bullets = tmp_enemy_bullets;
-
-            if !collided {
-                new_player_bullets.enqueue(player_bullet).unwrap();
+            GameState::LevelCompleted => {
+                LevelCompletedScreen { world: &self.world }.render(display)
             }
         }
 
-        self.player.bullets = new_player_bullets;
-        self.enemy.bullets = new_enemy_bullets;
-    }
-}
-
-fn detect_collison(a: Rectangle, b: Rectangle) -> bool {
-    let intersection = a.intersection(&b);
-
-    if intersection.size.width == 0 || intersection.size.height == 0 {
-        return false;
+        #[cfg(feature = "debug")]
+        FrameStatsOverlay {
+            stats: &self.frame_stats,
+        }
+        .render(display);
     }
-
-    true
 }
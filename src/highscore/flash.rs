@@ -0,0 +1,289 @@
+//! High-score table on NOR flash.
+//!
+//! The region is divided into fixed-size slots and every save goes to the
+//! slot after the last one, so each sector is only erased once all of its
+//! slots were used. A sector is erased just before its first slot is
+//! written, which always leaves the previous table intact in another sector
+//! should power fail halfway.
+//!
+//! Each slot holds one record:
+//!
+//! | bytes  | content                                          |
+//! |--------|--------------------------------------------------|
+//! | 4      | magic `CYHS`                                     |
+//! | 4      | sequence number, little endian                   |
+//! | 1      | entry count                                      |
+//! | 9 * 10 | entries of `(initials, score u32, level u16)`    |
+//! | 4      | CRC-32 of the bytes above, little endian         |
+//!
+//! Unused bytes are left erased. On load the valid record with the highest
+//! sequence number wins.
+
+use embedded_storage::nor_flash::NorFlash;
+use log::warn;
+
+use super::{Entry, HighScores, ScoreStorage, INITIALS_LEN, MAX_ENTRIES};
+
+const MAGIC: &[u8; 4] = b"CYHS";
+const ENTRY_LEN: usize = INITIALS_LEN + 4 + 2;
+const CRC_OFFSET: usize = 9 + ENTRY_LEN * MAX_ENTRIES;
+const RECORD_LEN: usize = CRC_OFFSET + 4;
+const SLOT_SIZE: usize = 128;
+const ERASED: u8 = 0xFF;
+
+const _: () = assert!(RECORD_LEN <= SLOT_SIZE);
+
+/// Wear-levelled [`ScoreStorage`] on a region of NOR flash, such as
+/// `esp_storage::FlashStorage` or [`MemoryFlash`](super::MemoryFlash).
+pub struct FlashScoreStorage<F> {
+    flash: F,
+    offset: u32,
+    slots: u32,
+    next_slot: u32,
+    next_sequence: u32,
+}
+
+impl<F: NorFlash> FlashScoreStorage<F> {
+    /// Uses the `size` bytes of `flash` from `offset` on. Both must be
+    /// multiples of the erase size and span at least two sectors.
+    pub fn new(flash: F, offset: u32, size: u32) -> Self {
+        assert!(offset as usize % F::ERASE_SIZE == 0 && size as usize % F::ERASE_SIZE == 0);
+        assert!(size as usize >= 2 * F::ERASE_SIZE);
+        assert!(F::ERASE_SIZE % SLOT_SIZE == 0 && SLOT_SIZE % F::WRITE_SIZE == 0);
+
+        Self {
+            flash,
+            offset,
+            slots: size / SLOT_SIZE as u32,
+            next_slot: 0,
+            next_sequence: 0,
+        }
+    }
+
+    fn slot_address(&self, slot: u32) -> u32 {
+        self.offset + slot * SLOT_SIZE as u32
+    }
+
+    fn read_slot(&mut self, slot: u32) -> Result<[u8; SLOT_SIZE], F::Error> {
+        let mut bytes = [0; SLOT_SIZE];
+        self.flash.read(self.slot_address(slot), &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Picks a blank slot, erasing the sector of the next one when the
+    /// current sector holds no blank slot.
+    fn prepare_slot(&mut self) -> Result<u32, F::Error> {
+        let slots_per_sector = (F::ERASE_SIZE / SLOT_SIZE) as u32;
+        let mut slot = self.next_slot;
+
+        // A write interrupted by a reset leaves a slot neither blank nor
+        // valid; skip the rest of its sector rather than erasing the sector
+        // holding the newest record
+        let is_sector_start = slot % slots_per_sector == 0;
+        if !is_sector_start && self.read_slot(slot)?.iter().any(|&b| b != ERASED) {
+            slot = (slot / slots_per_sector + 1) * slots_per_sector % self.slots;
+        }
+
+        if slot % slots_per_sector == 0 {
+            let from = self.slot_address(slot);
+            self.flash.erase(from, from + F::ERASE_SIZE as u32)?;
+        }
+
+        Ok(slot)
+    }
+
+    fn write(&mut self, scores: &HighScores) -> Result<(), F::Error> {
+        let slot = self.prepare_slot()?;
+        let bytes = encode(scores, self.next_sequence);
+        self.flash.write(self.slot_address(slot), &bytes)?;
+
+        self.next_slot = (slot + 1) % self.slots;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        Ok(())
+    }
+}
+
+impl<F: NorFlash> ScoreStorage for FlashScoreStorage<F> {
+    fn load(&mut self) -> HighScores {
+        let mut newest: Option<(u32, u32, HighScores)> = None;
+
+        for slot in 0..self.slots {
+            let bytes = match self.read_slot(slot) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    warn!("Failed to read high scores: {err:?}");
+                    continue;
+                }
+            };
+            let Some((sequence, scores)) = decode(&bytes) else {
+                continue;
+            };
+            match newest {
+                Some((_, newest_sequence, _)) if newest_sequence >= sequence => {}
+                _ => newest = Some((slot, sequence, scores)),
+            }
+        }
+
+        let Some((slot, sequence, scores)) = newest else {
+            return HighScores::new();
+        };
+
+        self.next_slot = (slot + 1) % self.slots;
+        self.next_sequence = sequence.wrapping_add(1);
+        scores
+    }
+
+    fn save(&mut self, scores: &HighScores) {
+        if let Err(err) = self.write(scores) {
+            warn!("Failed to save high scores: {err:?}");
+        }
+    }
+}
+
+fn encode(scores: &HighScores, sequence: u32) -> [u8; SLOT_SIZE] {
+    let mut bytes = [ERASED; SLOT_SIZE];
+
+    bytes[..4].copy_from_slice(MAGIC);
+    bytes[4..8].copy_from_slice(&sequence.to_le_bytes());
+    bytes[8] = scores.entries().len() as u8;
+
+    for (entry, chunk) in scores
+        .entries()
+        .iter()
+        .zip(bytes[9..CRC_OFFSET].chunks_exact_mut(ENTRY_LEN))
+    {
+        let level = entry.level.min(u16::MAX as u32) as u16;
+        chunk[..3].copy_from_slice(&entry.initials);
+        chunk[3..7].copy_from_slice(&entry.score.to_le_bytes());
+        chunk[7..9].copy_from_slice(&level.to_le_bytes());
+    }
+
+    let crc = crc32(&bytes[..CRC_OFFSET]);
+    bytes[CRC_OFFSET..RECORD_LEN].copy_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+fn decode(bytes: &[u8; SLOT_SIZE]) -> Option<(u32, HighScores)> {
+    let crc = u32::from_le_bytes(bytes[CRC_OFFSET..RECORD_LEN].try_into().unwrap());
+    if &bytes[..4] != MAGIC || crc32(&bytes[..CRC_OFFSET]) != crc {
+        return None;
+    }
+
+    let sequence = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let count = bytes[8] as usize;
+    if count > MAX_ENTRIES {
+        return None;
+    }
+
+    let mut scores = HighScores::new();
+    for chunk in bytes[9..CRC_OFFSET].chunks_exact(ENTRY_LEN).take(count) {
+        let entry = Entry {
+            initials: chunk[..3].try_into().unwrap(),
+            score: u32::from_le_bytes(chunk[3..7].try_into().unwrap()),
+            level: u16::from_le_bytes(chunk[7..9].try_into().unwrap()) as u32,
+        };
+        if !entry.initials.iter().all(u8::is_ascii_uppercase) {
+            return None;
+        }
+        scores.insert(entry)?;
+    }
+
+    Some((sequence, scores))
+}
+
+/// CRC-32 (IEEE 802.3), computed bitwise to keep the lookup table out of
+/// flash.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highscore::MemoryFlash;
+
+    const SIZE: usize = 2 * 4096;
+    const SLOTS: u32 = (SIZE / SLOT_SIZE) as u32;
+
+    fn storage() -> FlashScoreStorage<MemoryFlash<SIZE>> {
+        FlashScoreStorage::new(MemoryFlash::new(), 0, SIZE as u32)
+    }
+
+    /// Opens the flash of `storage` again, as after a reset.
+    fn reopen(
+        storage: FlashScoreStorage<MemoryFlash<SIZE>>,
+    ) -> FlashScoreStorage<MemoryFlash<SIZE>> {
+        FlashScoreStorage::new(storage.flash, 0, SIZE as u32)
+    }
+
+    fn table(score: u32) -> HighScores {
+        let mut scores = HighScores::new();
+        scores.insert(Entry {
+            initials: *b"ABC",
+            score,
+            level: 3,
+        });
+        scores
+    }
+
+    #[test]
+    fn saved_table_loads_after_reset() {
+        let mut storage = storage();
+        assert_eq!(storage.load(), HighScores::new());
+
+        storage.save(&table(120));
+
+        assert_eq!(reopen(storage).load(), table(120));
+    }
+
+    #[test]
+    fn saves_wrap_around_all_slots() {
+        let mut storage = storage();
+        for score in 1..=SLOTS + 5 {
+            storage.save(&table(score));
+        }
+
+        let mut storage = reopen(storage);
+        assert_eq!(storage.load(), table(SLOTS + 5));
+        assert_eq!(storage.next_slot, 5);
+    }
+
+    #[test]
+    fn corrupted_record_falls_back_to_the_previous_one() {
+        let mut storage = storage();
+        storage.save(&table(10));
+        storage.save(&table(20));
+
+        // Clear the bytes in front of the CRC of the second record
+        let crc_word = storage.slot_address(1) + (CRC_OFFSET / 4 * 4) as u32;
+        storage.flash.write(crc_word, &[0; 4]).unwrap();
+
+        assert_eq!(reopen(storage).load(), table(10));
+    }
+
+    #[test]
+    fn interrupted_write_skips_the_rest_of_its_sector() {
+        let mut storage = storage();
+        storage.save(&table(10));
+        let half_written = encode(&table(20), 1);
+        let address = storage.slot_address(1);
+        storage.flash.write(address, &half_written[..64]).unwrap();
+
+        let mut storage = reopen(storage);
+        assert_eq!(storage.load(), table(10));
+        storage.save(&table(30));
+
+        let slots_per_sector = (4096 / SLOT_SIZE) as u32;
+        let (_, scores) = decode(&storage.read_slot(slots_per_sector).unwrap()).unwrap();
+        assert_eq!(scores, table(30));
+        assert_eq!(reopen(storage).load(), table(30));
+    }
+}
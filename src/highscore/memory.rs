@@ -0,0 +1,70 @@
+use embedded_storage::nor_flash::{
+    check_erase, check_read, check_write, ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash,
+};
+
+/// NOR flash emulated in RAM, for the simulator and host-side checks.
+///
+/// Behaves like the real chip: erasing sets bits and writing can only clear
+/// them, so writes to a slot that wasn't erased corrupt it.
+pub struct MemoryFlash<const SIZE: usize> {
+    bytes: [u8; SIZE],
+}
+
+impl<const SIZE: usize> Default for MemoryFlash<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> MemoryFlash<SIZE> {
+    /// Creates a fully erased flash.
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0xFF; SIZE],
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<const SIZE: usize> ErrorType for MemoryFlash<SIZE> {
+    type Error = NorFlashErrorKind;
+}
+
+impl<const SIZE: usize> ReadNorFlash for MemoryFlash<SIZE> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_read(self, offset, bytes.len())?;
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.bytes[offset..offset + bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+impl<const SIZE: usize> NorFlash for MemoryFlash<SIZE> {
+    // Same as the ESP32's SPI flash
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = 4096;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_erase(self, from, to)?;
+        self.bytes[from as usize..to as usize].fill(0xFF);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_write(self, offset, bytes.len())?;
+        let offset = offset as usize;
+        for (stored, byte) in self.bytes[offset..].iter_mut().zip(bytes) {
+            *stored &= byte;
+        }
+        Ok(())
+    }
+}
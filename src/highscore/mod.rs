@@ -0,0 +1,234 @@
+//! Best scores of all time, with the initials of who made them.
+//!
+//! [`HighScores`] is the table itself and [`InitialsEntry`] the screen logic
+//! for typing initials after a run that made it into the table. Both live in
+//! RAM; a [`ScoreStorage`] keeps the table across resets, normally a
+//! [`FlashScoreStorage`] on a flash partition.
+
+use heapless::Vec;
+
+use crate::{input::MenuStick, world::Input};
+
+mod flash;
+mod memory;
+
+pub use flash::FlashScoreStorage;
+pub use memory::MemoryFlash;
+
+/// Number of scores kept.
+pub const MAX_ENTRIES: usize = 10;
+pub const INITIALS_LEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    /// Uppercase ASCII letters.
+    pub initials: [u8; INITIALS_LEN],
+    pub score: u32,
+    pub level: u32,
+}
+
+/// Entries sorted by descending score.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighScores {
+    entries: Vec<Entry, MAX_ENTRIES>,
+}
+
+impl HighScores {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Whether `score` would make it into the table.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (!self.entries.is_full() || self.entries.last().is_some_and(|e| score > e.score))
+    }
+
+    /// Inserts `entry` below any equal score, dropping the lowest entry if
+    /// the table is full. Returns its rank, `None` if it didn't qualify.
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+
+        let rank = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+
+        if self.entries.is_full() {
+            self.entries.pop();
+        }
+        self.entries.insert(rank, entry).ok()?;
+
+        Some(rank)
+    }
+}
+
+/// Keeps the high-score table across resets.
+pub trait ScoreStorage {
+    /// Returns the last saved table, or an empty one if none was saved or
+    /// it can't be read.
+    fn load(&mut self) -> HighScores;
+
+    /// Saves `scores`. Failures are logged, the game goes on without them.
+    fn save(&mut self, scores: &HighScores);
+}
+
+/// Picks three initials with the stick: up and down change the letter, the
+/// main button confirms it and moves on to the next one.
+pub struct InitialsEntry {
+    initials: [u8; INITIALS_LEN],
+    cursor: usize,
+    stick: MenuStick,
+}
+
+impl Default for InitialsEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InitialsEntry {
+    pub fn new() -> Self {
+        Self {
+            initials: [b'A'; INITIALS_LEN],
+            cursor: 0,
+            stick: MenuStick::new(),
+        }
+    }
+
+    pub fn initials(&self) -> &[u8; INITIALS_LEN] {
+        &self.initials
+    }
+
+    /// Index of the letter being picked.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Advances by one frame. Returns the initials once the last letter is
+    /// confirmed.
+    pub fn step(&mut self, input: Input) -> Option<[u8; INITIALS_LEN]> {
+        if self.cursor == INITIALS_LEN {
+            return Some(self.initials);
+        }
+
+        if input.fire {
+            self.cursor += 1;
+            self.stick.reset();
+            if self.cursor == INITIALS_LEN {
+                return Some(self.initials);
+            }
+            return None;
+        }
+
+        // Up goes forward through the alphabet
+        let letter = &mut self.initials[self.cursor];
+        let index = *letter - b'A';
+        match self.stick.step(input.y_axis) {
+            -1 => *letter = b'A' + (index + 1) % 26,
+            1 => *letter = b'A' + (index + 25) % 26,
+            _ => {}
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::AXIS_MAX;
+
+    const IDLE: Input = Input {
+        x_axis: 0,
+        y_axis: 0,
+        fire: false,
+        fire_held: false,
+        pause: false,
+    };
+    const CONFIRM: Input = Input { fire: true, ..IDLE };
+    const UP: Input = Input {
+        y_axis: -AXIS_MAX,
+        ..IDLE
+    };
+    const DOWN: Input = Input {
+        y_axis: AXIS_MAX,
+        ..IDLE
+    };
+
+    fn entry(initials: &[u8; INITIALS_LEN], score: u32) -> Entry {
+        Entry {
+            initials: *initials,
+            score,
+            level: 1,
+        }
+    }
+
+    #[test]
+    fn tie_goes_below_the_existing_score() {
+        let mut scores = HighScores::new();
+        scores.insert(entry(b"AAA", 50));
+        scores.insert(entry(b"CCC", 10));
+
+        assert_eq!(scores.insert(entry(b"BBB", 50)), Some(1));
+        let initials: Vec<_, MAX_ENTRIES> = scores.entries().iter().map(|e| e.initials).collect();
+        assert_eq!(initials, [*b"AAA", *b"BBB", *b"CCC"]);
+    }
+
+    #[test]
+    fn full_table_drops_its_last_entry() {
+        let mut scores = HighScores::new();
+        for score in 1..=MAX_ENTRIES as u32 {
+            scores.insert(entry(b"AAA", score * 10));
+        }
+
+        assert_eq!(scores.insert(entry(b"BBB", 55)), Some(5));
+        assert_eq!(scores.entries().len(), MAX_ENTRIES);
+        assert_eq!(scores.entries().last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn low_scores_do_not_qualify() {
+        let mut scores = HighScores::new();
+        assert_eq!(scores.insert(entry(b"AAA", 0)), None);
+
+        for score in 1..=MAX_ENTRIES as u32 {
+            scores.insert(entry(b"AAA", score * 10));
+        }
+        assert_eq!(scores.insert(entry(b"BBB", 10)), None);
+        assert_eq!(scores.insert(entry(b"BBB", 5)), None);
+        assert!(scores.entries().iter().all(|e| e.initials == *b"AAA"));
+    }
+
+    #[test]
+    fn letters_wrap_around() {
+        let mut entry = InitialsEntry::new();
+        entry.step(DOWN);
+        assert_eq!(entry.initials()[0], b'Z');
+
+        entry.step(IDLE);
+        entry.step(UP);
+        assert_eq!(entry.initials()[0], b'A');
+    }
+
+    #[test]
+    fn third_confirm_returns_the_initials() {
+        let mut entry = InitialsEntry::new();
+        assert_eq!(entry.step(UP), None);
+        assert_eq!(entry.step(CONFIRM), None);
+        assert_eq!(entry.step(CONFIRM), None);
+        assert_eq!(entry.cursor(), 2);
+
+        assert_eq!(entry.step(CONFIRM), Some(*b"BAA"));
+        assert_eq!(entry.step(IDLE), Some(*b"BAA"));
+    }
+}
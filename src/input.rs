@@ -0,0 +1,286 @@
+//! Control events, from the tasks reading the hardware to the game loop.
+//!
+//! The control tasks (or the simulator's keyboard thread) turn raw button and
+//! stick readings into timestamped [`InputEvent`]s on [`INPUT_EVENTS`]. The
+//! game drains the channel once per frame, so presses are never lost between
+//! frames nor counted twice, and stale ones can be told apart by their time.
+//!
+//! The stick position itself is still sampled through
+//! [`PLAYER_X_AXIS`](crate::player::PLAYER_X_AXIS) and
+//! [`PLAYER_Y_AXIS`](crate::player::PLAYER_Y_AXIS), as movement follows its
+//! deflection rather than its edges.
+
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex,
+    channel::{Channel, TrySendError},
+};
+use embassy_time::{Duration, Instant};
+
+use crate::player::AXIS_MAX;
+
+/// Holding the main button this long is a long press.
+pub const LONG_PRESS: Duration = Duration::from_millis(600);
+/// A press this soon after the previous release is a double tap.
+pub const DOUBLE_TAP: Duration = Duration::from_millis(300);
+// Events published within a frame or two; past that the oldest are dropped
+const EVENT_QUEUE_SIZE: usize = 16;
+// Stick deflection that counts as pointing in a direction, and how many
+// frames a held stick waits before stepping through a menu again
+const STICK_THRESHOLD: i8 = AXIS_MAX / 2;
+const MENU_REPEAT_FRAMES: u8 = 8;
+
+pub static INPUT_EVENTS: Channel<CriticalSectionRawMutex, InputEvent, EVENT_QUEUE_SIZE> =
+    Channel::new();
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEventKind {
+    /// The main button went down.
+    Pressed,
+    /// The main button went up.
+    Released,
+    /// The main button has been held for [`LONG_PRESS`]. Sent once per hold.
+    LongPress,
+    /// The main button went down within [`DOUBLE_TAP`] of its last release,
+    /// right after the matching `Pressed`.
+    DoubleTap,
+    /// The stick or direction buttons now point somewhere else.
+    Direction(Direction),
+    /// The pause button went down.
+    Pause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputEvent {
+    pub kind: InputEventKind,
+    pub at: Instant,
+}
+
+/// Where the controls point, each axis -1, 0 or 1 with the signs of
+/// [`PLAYER_X_AXIS`](crate::player::PLAYER_X_AXIS) and
+/// [`PLAYER_Y_AXIS`](crate::player::PLAYER_Y_AXIS).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Direction {
+    pub x: i8,
+    pub y: i8,
+}
+
+impl Direction {
+    pub fn from_axes(x_axis: i8, y_axis: i8) -> Self {
+        let sign = |axis: i8| {
+            if axis.abs() >= STICK_THRESHOLD {
+                axis.signum()
+            } else {
+                0
+            }
+        };
+        Self {
+            x: sign(x_axis),
+            y: sign(y_axis),
+        }
+    }
+}
+
+/// Queues an event that happened at `at`. If nobody drained the queue, the
+/// oldest event makes room for it, so the last `Released` is never lost and
+/// the game always learns the button is up.
+pub fn publish(kind: InputEventKind, at: Instant) {
+    let mut event = InputEvent { kind, at };
+    while let Err(TrySendError::Full(rejected)) = INPUT_EVENTS.try_send(event) {
+        let _ = INPUT_EVENTS.try_receive();
+        event = rejected;
+    }
+}
+
+/// Turns the state of the main button over time into press, release, long
+/// press and double tap events.
+pub struct ButtonTracker {
+    pressed_at: Option<Instant>,
+    long_press_sent: bool,
+    released_at: Option<Instant>,
+}
+
+impl Default for ButtonTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ButtonTracker {
+    pub const fn new() -> Self {
+        Self {
+            pressed_at: None,
+            long_press_sent: false,
+            released_at: None,
+        }
+    }
+
+    /// Feeds the debounced state of the button at `now` and publishes the
+    /// events it causes.
+    pub fn update(&mut self, pressed: bool, now: Instant) {
+        self.track(pressed, now, |kind| publish(kind, now));
+    }
+
+    fn track(&mut self, pressed: bool, now: Instant, mut emit: impl FnMut(InputEventKind)) {
+        match (pressed, self.pressed_at) {
+            (true, None) => {
+                self.pressed_at = Some(now);
+                emit(InputEventKind::Pressed);
+
+                if let Some(released_at) = self.released_at.take() {
+                    if now - released_at <= DOUBLE_TAP {
+                        emit(InputEventKind::DoubleTap);
+                    }
+                }
+            }
+            (true, Some(pressed_at)) => {
+                if !self.long_press_sent && now - pressed_at >= LONG_PRESS {
+                    self.long_press_sent = true;
+                    emit(InputEventKind::LongPress);
+                }
+            }
+            (false, Some(_)) => {
+                self.pressed_at = None;
+                self.long_press_sent = false;
+                self.released_at = Some(now);
+                emit(InputEventKind::Released);
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+/// Publishes a [`Direction`](InputEventKind::Direction) event whenever the
+/// controls point somewhere new.
+#[derive(Default)]
+pub struct DirectionTracker {
+    last: Direction,
+}
+
+impl DirectionTracker {
+    pub const fn new() -> Self {
+        Self {
+            last: Direction { x: 0, y: 0 },
+        }
+    }
+
+    pub fn update(&mut self, x_axis: i8, y_axis: i8, now: Instant) {
+        let direction = Direction::from_axes(x_axis, y_axis);
+        if direction != self.last {
+            self.last = direction;
+            publish(InputEventKind::Direction(direction), now);
+        }
+    }
+}
+
+/// Steps through a menu with the vertical stick: once when it is pushed, then
+/// again every [`MENU_REPEAT_FRAMES`] frames while it is held.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct MenuStick {
+    // Frames until a held stick steps again
+    repeat_in: u8,
+}
+
+impl MenuStick {
+    pub const fn new() -> Self {
+        Self { repeat_in: 0 }
+    }
+
+    /// Advances by one frame. Returns -1 to step up, 1 to step down and 0
+    /// to stay.
+    pub fn step(&mut self, y_axis: i8) -> i8 {
+        if y_axis.abs() < STICK_THRESHOLD {
+            self.repeat_in = 0;
+            return 0;
+        }
+
+        if self.repeat_in > 0 {
+            self.repeat_in -= 1;
+            return 0;
+        }
+        self.repeat_in = MENU_REPEAT_FRAMES;
+
+        y_axis.signum()
+    }
+
+    /// Makes a stick that is still held step on the next frame.
+    pub fn reset(&mut self) {
+        self.repeat_in = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `(milliseconds, pressed)` samples to a new tracker, returning
+    /// the events with the time they were sent at.
+    fn track(samples: &[(u64, bool)]) -> heapless::Vec<(u64, InputEventKind), 16> {
+        let mut tracker = ButtonTracker::new();
+        let mut events = heapless::Vec::new();
+        for &(ms, pressed) in samples {
+            let now = Instant::from_millis(ms);
+            tracker.track(pressed, now, |kind| events.push((ms, kind)).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn long_press_fires_once_per_hold() {
+        use InputEventKind::*;
+
+        let mut samples: heapless::Vec<(u64, bool), 512> =
+            (0..=1500).step_by(5).map(|ms| (ms, true)).collect();
+        samples.push((1505, false)).unwrap();
+        samples.push((2000, true)).unwrap();
+        samples.push((2700, true)).unwrap();
+
+        assert_eq!(
+            track(&samples),
+            [
+                (0, Pressed),
+                (600, LongPress),
+                (1505, Released),
+                (2000, Pressed),
+                (2700, LongPress)
+            ]
+        );
+    }
+
+    #[test]
+    fn double_tap_fires_within_the_window() {
+        use InputEventKind::*;
+
+        let tapped = track(&[(0, true), (50, false), (350, true), (400, false)]);
+        assert_eq!(
+            tapped,
+            [
+                (0, Pressed),
+                (50, Released),
+                (350, Pressed),
+                (350, DoubleTap),
+                (400, Released)
+            ]
+        );
+
+        let too_slow = track(&[(0, true), (50, false), (351, true)]);
+        assert_eq!(too_slow, [(0, Pressed), (50, Released), (351, Pressed)]);
+    }
+
+    #[test]
+    fn full_queue_keeps_the_latest_release() {
+        let now = Instant::from_ticks(0);
+        for _ in 0..EVENT_QUEUE_SIZE {
+            publish(InputEventKind::Pressed, now);
+        }
+        publish(InputEventKind::Released, now);
+
+        let mut last = None;
+        let mut count = 0;
+        while let Ok(event) = INPUT_EVENTS.try_receive() {
+            last = Some(event.kind);
+            count += 1;
+        }
+        assert_eq!(count, EVENT_QUEUE_SIZE);
+        assert_eq!(last, Some(InputEventKind::Released));
+    }
+}
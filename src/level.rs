@@ -0,0 +1,223 @@
+//! Difficulty progression.
+//!
+//! Every level is described by a row of [`LEVELS`]; tune the game by editing
+//! the table. Past its end the last [`REPEATED_LEVELS`] rows are played over
+//! and over, while enemies keep gaining hit points.
+//!
+//! Every fifth row is a boss fight: the level is a single [`Boss`] and ends
+//! when it is destroyed.
+
+use crate::{
+    enemy::{
+        EnemyKind::{self, *},
+        MAX_ENEMY_BULLETS,
+    },
+    player::MAX_PLAYER_BULLETS,
+};
+
+/// Rows at the end of [`LEVELS`] that repeat once the table runs out.
+pub const REPEATED_LEVELS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelDef {
+    /// Points to score during the level to complete it. Unused in boss
+    /// levels.
+    pub score: u32,
+    /// Enemies met in the level. The first one opens the level, the next
+    /// ones are picked at random from the whole list. Unused in boss levels.
+    pub enemies: &'static [EnemyKind],
+    /// Enemy movement, in pixels per frame.
+    pub enemy_speed: i32,
+    /// Enemy bullet speed, in pixels per frame.
+    pub bullet_speed: i32,
+    /// Enemy bullets on screen at once. Bosses fire volleys past it.
+    pub max_enemy_bullets: usize,
+    /// Player bullets on screen at once.
+    pub max_player_bullets: usize,
+    /// Frames between player shots, whether the button is tapped or held.
+    /// Rapid fire halves it.
+    pub fire_cooldown: u32,
+    /// Whether the level is a boss fight.
+    pub boss: bool,
+}
+
+impl LevelDef {
+    /// Enemy met first in the level.
+    pub fn opening_enemy(&self) -> EnemyKind {
+        if self.boss {
+            Boss
+        } else {
+            self.enemies[0]
+        }
+    }
+}
+
+pub const LEVELS: [LevelDef; 10] = [
+    LevelDef {
+        score: 50,
+        enemies: &[PlanetKiller],
+        enemy_speed: 1,
+        bullet_speed: 3,
+        max_enemy_bullets: 1,
+        max_player_bullets: 1,
+        fire_cooldown: 10,
+        boss: false,
+    },
+    LevelDef {
+        score: 50,
+        enemies: &[Fighter, PlanetKiller],
+        enemy_speed: 2,
+        bullet_speed: 3,
+        max_enemy_bullets: 2,
+        max_player_bullets: 2,
+        fire_cooldown: 9,
+        boss: false,
+    },
+    LevelDef {
+        score: 50,
+        enemies: &[Turret, PlanetKiller, Fighter],
+        enemy_speed: 3,
+        bullet_speed: 3,
+        max_enemy_bullets: 3,
+        max_player_bullets: 3,
+        fire_cooldown: 8,
+        boss: false,
+    },
+    LevelDef {
+        score: 50,
+        enemies: &[Kamikaze, PlanetKiller, Fighter, Turret],
+        enemy_speed: 4,
+        bullet_speed: 3,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 8,
+        boss: false,
+    },
+    LevelDef {
+        score: 0,
+        enemies: &[],
+        enemy_speed: 1,
+        bullet_speed: 3,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 8,
+        boss: true,
+    },
+    LevelDef {
+        score: 60,
+        enemies: &[Fighter, Turret],
+        enemy_speed: 5,
+        bullet_speed: 4,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 7,
+        boss: false,
+    },
+    LevelDef {
+        score: 70,
+        enemies: &[Turret, Kamikaze, Fighter],
+        enemy_speed: 5,
+        bullet_speed: 4,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 7,
+        boss: false,
+    },
+    LevelDef {
+        score: 70,
+        enemies: &[Kamikaze, Fighter, PlanetKiller],
+        enemy_speed: 6,
+        bullet_speed: 5,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 6,
+        boss: false,
+    },
+    LevelDef {
+        score: 80,
+        enemies: &[PlanetKiller, Fighter, Turret, Kamikaze],
+        enemy_speed: 6,
+        bullet_speed: 5,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 6,
+        boss: false,
+    },
+    LevelDef {
+        score: 0,
+        enemies: &[],
+        enemy_speed: 1,
+        bullet_speed: 4,
+        max_enemy_bullets: 4,
+        max_player_bullets: 4,
+        fire_cooldown: 6,
+        boss: true,
+    },
+];
+
+// A broken table fails the build instead of the game
+const _: () = validate(&LEVELS);
+
+/// Settings of `level`, counting from 1.
+pub fn def(level: u32) -> &'static LevelDef {
+    let index = level.max(1) as usize - 1;
+    if index < LEVELS.len() {
+        return &LEVELS[index];
+    }
+
+    let first_repeated = LEVELS.len() - REPEATED_LEVELS;
+    &LEVELS[first_repeated + (index - first_repeated) % REPEATED_LEVELS]
+}
+
+const fn validate(levels: &[LevelDef]) {
+    assert!(levels.len() >= REPEATED_LEVELS && REPEATED_LEVELS > 0);
+
+    let mut i = 0;
+    while i < levels.len() {
+        let level = &levels[i];
+        assert!(
+            level.boss || level.score > 0,
+            "a level must need some points"
+        );
+        assert!(
+            level.boss || !level.enemies.is_empty(),
+            "a level needs enemies"
+        );
+        assert!(level.enemy_speed > 0 && level.bullet_speed > 0);
+        assert!(level.max_enemy_bullets > 0 && level.max_enemy_bullets <= MAX_ENEMY_BULLETS);
+        assert!(level.max_player_bullets > 0 && level.max_player_bullets <= MAX_PLAYER_BULLETS);
+        assert!(level.fire_cooldown > 0);
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_past_the_table_repeat_its_last_rows() {
+        let len = LEVELS.len() as u32;
+        let first_repeated = len - REPEATED_LEVELS as u32 + 1;
+
+        assert_eq!(def(0), &LEVELS[0]);
+        assert_eq!(def(len), &LEVELS[LEVELS.len() - 1]);
+        for level in len + 1..=len + 3 * REPEATED_LEVELS as u32 {
+            let repeated = first_repeated + (level - first_repeated) % REPEATED_LEVELS as u32;
+            assert_eq!(def(level), def(repeated), "level {level}");
+        }
+    }
+
+    #[test]
+    fn every_fifth_level_is_a_boss_fight() {
+        for level in 1..=4 * LEVELS.len() as u32 {
+            let def = def(level);
+            assert_eq!(def.boss, level % 5 == 0, "level {level}");
+            if def.boss {
+                assert_eq!(def.opening_enemy(), Boss);
+            } else {
+                assert_eq!(def.opening_enemy(), def.enemies[0], "level {level}");
+            }
+        }
+    }
+}
@@ -1,8 +1,22 @@
 #![no_std]
 
 pub mod audio;
+mod collision;
+#[cfg(feature = "esp32")]
 pub mod control;
+pub mod display;
 mod enemy;
 pub mod game;
-mod player;
+pub mod highscore;
+pub mod input;
+pub mod level;
+pub mod player;
+pub mod powerup;
+pub mod replay;
+pub mod rng;
+mod screen;
+#[cfg(not(feature = "esp32"))]
+pub mod sim;
 mod sprites;
+pub mod timing;
+pub mod world;
@@ -1,32 +1,57 @@
-use core::sync::atomic::Ordering;
+use core::fmt::Debug;
+use core::sync::atomic::AtomicI8;
 
-use atomic_enum::atomic_enum;
 use embedded_graphics::{
     image::Image,
     pixelcolor::BinaryColor,
     prelude::*,
-    primitives::{PrimitiveStyle, Rectangle},
+    primitives::{Circle, PrimitiveStyle, Rectangle},
 };
 use heapless::spsc::Queue;
 
-use crate::game::DisplayType;
+use crate::{
+    collision::{sprite_hit, Hitbox},
+    level,
+    powerup::PowerUpKind,
+    sprites::MASK_PLAYER_JET,
+};
 
-#[atomic_enum]
-#[derive(PartialEq)]
-pub enum PlayerDirection {
-    Up,
-    Down,
-    Idle,
-}
+/// Full deflection of a movement axis. Digital controls (buttons, keyboard)
+/// report either 0 or `±AXIS_MAX`.
+pub const AXIS_MAX: i8 = 100;
 
-pub static PLAYER_DIRECTION: AtomicPlayerDirection =
-    AtomicPlayerDirection::new(PlayerDirection::Idle);
-const PLAYER_LIVES: u8 = 3;
+/// Vertical control, from `-AXIS_MAX` (full up) to `AXIS_MAX` (full down).
+pub static PLAYER_Y_AXIS: AtomicI8 = AtomicI8::new(0);
+/// Horizontal control, from `-AXIS_MAX` (full back) to `AXIS_MAX` (full
+/// forward).
+pub static PLAYER_X_AXIS: AtomicI8 = AtomicI8::new(0);
+pub const PLAYER_LIVES: u8 = 3;
 const BULLET_SIZE: Size = Size::new(5, 2);
-const MAX_PLAYER_BULLETS: usize = 4;
-const BULLET_QUEUE_SIZE: usize = MAX_PLAYER_BULLETS + 1;
+pub const MAX_PLAYER_BULLETS: usize = 4;
+// Extra bullets on screen with rapid fire, and bullets per spread shot
+const RAPID_FIRE_BULLETS: usize = 2;
+const SPREAD_BULLETS: usize = 3;
+const BULLET_QUEUE_SIZE: usize = (MAX_PLAYER_BULLETS + RAPID_FIRE_BULLETS) * SPREAD_BULLETS + 1;
 const INITIAL_BULLET_SPEED: i32 = 3;
 const INITIAL_PLAYER_SPEED: i32 = 3;
+/// Frames the player can't be hit after losing a life or their shield.
+pub const INVINCIBILITY_FRAMES: u32 = 90;
+// The ship blinks while invincible, switching every this many frames
+const BLINK_FRAMES: u32 = 4;
+
+/// A bullet flying right, and up or down for the outer ones of a spread
+/// shot.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerBullet {
+    pub shape: Rectangle,
+    dy: i32,
+}
+
+impl Dimensions for PlayerBullet {
+    fn bounding_box(&self) -> Rectangle {
+        self.shape
+    }
+}
 
 pub struct Player {
     // Display Resolution
@@ -34,12 +59,25 @@ pub struct Player {
     screen_height: i32,
     // Player Data
     pub img: Image<'static, super::sprites::ImgRawType>,
+    // Pixels per frame at full deflection
     speed: i32,
+    // Movement below a whole pixel, in 1/AXIS_MAX pixels
+    x_remainder: i32,
+    y_remainder: i32,
     pub lives: u8,
     // Bullets Data
-    pub bullets: Queue<Rectangle, BULLET_QUEUE_SIZE>,
+    pub bullets: Queue<PlayerBullet, BULLET_QUEUE_SIZE>,
     bullet_speed: i32,
     max_bullet: usize,
+    // Frames between shots, and left until the next one
+    fire_cooldown: u32,
+    cooldown_frames: u32,
+    // Frames left until it can be hit again
+    invincible_frames: u32,
+    // Frames left of each power-up effect
+    shield_frames: u32,
+    rapid_fire_frames: u32,
+    spread_frames: u32,
 }
 
 impl Player {
@@ -57,51 +95,145 @@ impl Player {
             screen_width,
             screen_height,
             speed: INITIAL_PLAYER_SPEED,
+            x_remainder: 0,
+            y_remainder: 0,
             bullets: Queue::new(),
             bullet_speed: INITIAL_BULLET_SPEED,
-            max_bullet: 1,
+            max_bullet: level::def(1).max_player_bullets,
+            fire_cooldown: level::def(1).fire_cooldown,
+            cooldown_frames: 0,
+            invincible_frames: 0,
+            shield_frames: 0,
+            rapid_fire_frames: 0,
+            spread_frames: 0,
+        }
+    }
+
+    /// Applies the settings of `level`, see [`level::LEVELS`].
+    pub fn set_level(&mut self, level: u32) {
+        let def = level::def(level);
+        self.max_bullet = def.max_player_bullets;
+        self.fire_cooldown = def.fire_cooldown;
+    }
+
+    /// Grants the effect of a collected power-up, restarting it if it was
+    /// already active.
+    pub fn power_up(&mut self, kind: PowerUpKind) {
+        match kind {
+            PowerUpKind::Shield => self.shield_frames = kind.duration(),
+            PowerUpKind::RapidFire => self.rapid_fire_frames = kind.duration(),
+            PowerUpKind::Spread => self.spread_frames = kind.duration(),
+            PowerUpKind::ExtraLife => self.lives = (self.lives + 1).min(PLAYER_LIVES),
+        }
+    }
+
+    /// Frames left of the `kind` effect, 0 if it isn't active.
+    pub fn effect_frames(&self, kind: PowerUpKind) -> u32 {
+        match kind {
+            PowerUpKind::Shield => self.shield_frames,
+            PowerUpKind::RapidFire => self.rapid_fire_frames,
+            PowerUpKind::Spread => self.spread_frames,
+            PowerUpKind::ExtraLife => 0,
         }
     }
 
-    pub fn increase_level(&mut self) {
-        self.max_bullet = (self.max_bullet + 1).min(MAX_PLAYER_BULLETS);
+    /// Whether `hitbox` touches the ship, see [`sprite_hit`].
+    pub fn is_hit_by(&self, hitbox: Hitbox) -> bool {
+        sprite_hit(&MASK_PLAYER_JET, self.img.bounding_box().top_left, hitbox)
+    }
+
+    /// Whether hits are ignored, after one just landed.
+    pub fn is_invincible(&self) -> bool {
+        self.invincible_frames > 0
+    }
+
+    /// Ignores hits for the next [`INVINCIBILITY_FRAMES`].
+    pub fn make_invincible(&mut self) {
+        self.invincible_frames = INVINCIBILITY_FRAMES;
+    }
+
+    /// Uses up the shield to take a hit. Returns false if there is none and
+    /// the hit costs a life.
+    pub fn absorb_hit(&mut self) -> bool {
+        if self.shield_frames == 0 {
+            return false;
+        }
+        self.shield_frames = 0;
+        true
     }
 
-    pub fn draw(&self, display: &mut DisplayType) {
-        self.img.draw(display).unwrap();
+    pub fn draw<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let blink_off = self.invincible_frames / BLINK_FRAMES % 2 == 1;
+        if !blink_off {
+            self.img.draw(display).unwrap();
+        }
+        if self.shield_frames > 0 {
+            let bounding_box = self.img.bounding_box();
+            Circle::with_center(bounding_box.center(), bounding_box.size.width + 4)
+                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                .draw(display)
+                .unwrap();
+        }
         self.draw_bullet(display);
     }
 
-    pub fn draw_bullet(&self, display: &mut DisplayType) {
+    pub fn draw_bullet<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
         self.bullets.iter().for_each(|bullet| {
             bullet
+                .shape
                 .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
                 .draw(display)
                 .unwrap();
         });
     }
 
-    pub fn update(&mut self) {
-        self.update_position();
+    pub fn update(&mut self, x_axis: i8, y_axis: i8) {
+        self.update_position(x_axis, y_axis);
         self.update_bullet();
-    }
 
-    fn update_position(&mut self) {
-        let direction = PLAYER_DIRECTION.load(Ordering::Relaxed);
+        self.cooldown_frames = self.cooldown_frames.saturating_sub(1);
+        self.invincible_frames = self.invincible_frames.saturating_sub(1);
+        self.shield_frames = self.shield_frames.saturating_sub(1);
+        self.rapid_fire_frames = self.rapid_fire_frames.saturating_sub(1);
+        self.spread_frames = self.spread_frames.saturating_sub(1);
+    }
 
+    /// Moves the ship proportionally to the deflection of the axes, keeping
+    /// it within the left third of the screen.
+    fn update_position(&mut self, x_axis: i8, y_axis: i8) {
         let bounding_box = self.img.bounding_box();
 
-        let y = bounding_box.top_left.y;
+        let Point { x, y } = bounding_box.top_left;
         let bound_pad = 5;
-        let max_bound = self.screen_height - bound_pad - bounding_box.size.height as i32;
+        let max_x = self.screen_width / 3 - bounding_box.size.width as i32;
+        let max_y = self.screen_height - bound_pad - bounding_box.size.height as i32;
 
-        let new_y = match direction {
-            PlayerDirection::Idle => y,
-            PlayerDirection::Up => (y - self.speed).max(bound_pad),
-            PlayerDirection::Down => (y + self.speed).min(max_bound),
-        };
+        let new_x = move_along(
+            x,
+            &mut self.x_remainder,
+            x_axis,
+            self.speed,
+            bound_pad,
+            max_x,
+        );
+        let new_y = move_along(
+            y,
+            &mut self.y_remainder,
+            y_axis,
+            self.speed,
+            bound_pad,
+            max_y,
+        );
 
-        let shift_by = Point::new(0, new_y - y);
+        let shift_by = Point::new(new_x - x, new_y - y);
 
         self.img = self.img.translate(shift_by);
     }
@@ -111,11 +243,17 @@ impl Player {
             return;
         }
 
-        let mut new_queue = Queue::<Rectangle, BULLET_QUEUE_SIZE>::new();
+        let mut new_queue = Queue::<PlayerBullet, BULLET_QUEUE_SIZE>::new();
 
-        while let Some(bullet) = self.bullets.dequeue() {
-            let bullet = bullet.translate(Point::new(self.bullet_speed, 0));
-            if bullet.top_left.x > self.screen_width {
+        while let Some(mut bullet) = self.bullets.dequeue() {
+            bullet.shape = bullet
+                .shape
+                .translate(Point::new(self.bullet_speed, bullet.dy));
+            let Point { x, y } = bullet.shape.top_left;
+            if x > self.screen_width
+                || y >= self.screen_height
+                || y + (BULLET_SIZE.height as i32) <= 0
+            {
                 continue;
             }
             new_queue.enqueue(bullet).unwrap()
@@ -123,8 +261,26 @@ impl Player {
         self.bullets = new_queue;
     }
 
+    /// Fires a bullet, or a spread of them. Returns false if the gun is
+    /// still cooling down from the last shot or too many are already on
+    /// screen.
     pub fn shoot(&mut self) -> bool {
-        if self.bullets.is_full() || self.bullets.len() >= self.max_bullet {
+        if self.cooldown_frames > 0 {
+            return false;
+        }
+
+        let mut max_bullet = self.max_bullet;
+        let mut cooldown = self.fire_cooldown;
+        if self.rapid_fire_frames > 0 {
+            max_bullet += RAPID_FIRE_BULLETS;
+            cooldown = cooldown.div_ceil(2);
+        }
+        let (count, spread) = if self.spread_frames > 0 {
+            (SPREAD_BULLETS, SPREAD_BULLETS as i32 / 2)
+        } else {
+            (1, 0)
+        };
+        if self.bullets.len() + count > max_bullet * count {
             return false;
         }
 
@@ -136,10 +292,32 @@ impl Player {
             player_pos.x + player_size.width as i32,
             player_pos.y + player_size.height as i32 / 2,
         );
-        let bullet = Rectangle::new(pos, BULLET_SIZE);
-
-        self.bullets.enqueue(bullet).unwrap();
+        for dy in -spread..=spread {
+            let bullet = PlayerBullet {
+                shape: Rectangle::new(pos, BULLET_SIZE),
+                dy,
+            };
+            self.bullets.enqueue(bullet).unwrap();
+        }
+        self.cooldown_frames = cooldown;
 
         true
     }
 }
+
+/// Moves `position` by `speed * axis / AXIS_MAX` pixels, clamped to
+/// `min..=max`.
+///
+/// Fractions of a pixel are carried over in `remainder` so small deflections
+/// still move the ship, just slowly.
+fn move_along(position: i32, remainder: &mut i32, axis: i8, speed: i32, min: i32, max: i32) -> i32 {
+    *remainder += axis as i32 * speed;
+    let delta = *remainder / AXIS_MAX as i32;
+    *remainder %= AXIS_MAX as i32;
+
+    let new_position = (position + delta).clamp(min, max);
+    if new_position != position + delta {
+        *remainder = 0;
+    }
+    new_position
+}
@@ -0,0 +1,112 @@
+//! Items drifting in from the right edge that the player collects by flying
+//! into them.
+//!
+//! Most of them grant an effect for a while, tracked in
+//! [`Player`](crate::player::Player); the extra life is handed out at once.
+
+use core::fmt::Debug;
+
+use embedded_graphics::{
+    image::Image,
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+};
+
+use crate::sprites::{ImgRawType, RAW_HEART, RAW_RAPID_FIRE, RAW_SHIELD, RAW_SPREAD};
+
+// Pixels per frame the item drifts left
+const DRIFT_SPEED: i32 = 1;
+// Room between the icon and the ring around it
+const RING_PADDING: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerUpKind {
+    /// Absorbs the next hit.
+    Shield,
+    /// More player bullets on screen at once.
+    RapidFire,
+    /// Every shot fires three bullets in a fan.
+    Spread,
+    /// One more life, up to the starting count.
+    ExtraLife,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::Shield,
+        PowerUpKind::RapidFire,
+        PowerUpKind::Spread,
+        PowerUpKind::ExtraLife,
+    ];
+
+    /// Frames the effect lasts, 0 for the extra life.
+    pub fn duration(self) -> u32 {
+        match self {
+            PowerUpKind::Shield => 600,
+            PowerUpKind::RapidFire => 450,
+            PowerUpKind::Spread => 450,
+            PowerUpKind::ExtraLife => 0,
+        }
+    }
+
+    /// Icon of the item, also shown in the HUD while the effect lasts.
+    pub fn icon(self) -> &'static ImgRawType {
+        match self {
+            PowerUpKind::Shield => &RAW_SHIELD,
+            PowerUpKind::RapidFire => &RAW_RAPID_FIRE,
+            PowerUpKind::Spread => &RAW_SPREAD,
+            PowerUpKind::ExtraLife => &RAW_HEART,
+        }
+    }
+}
+
+pub struct PowerUp {
+    kind: PowerUpKind,
+    pub img: Image<'static, ImgRawType>,
+}
+
+impl PowerUp {
+    /// Places a `kind` item just past the right edge, centred on `y`.
+    pub fn new(kind: PowerUpKind, y: i32, screen_width: i32) -> Self {
+        let size = kind.icon().size();
+        let position = Point::new(screen_width, y - size.height as i32 / 2);
+
+        Self {
+            kind,
+            img: Image::new(kind.icon(), position),
+        }
+    }
+
+    pub fn kind(&self) -> PowerUpKind {
+        self.kind
+    }
+
+    pub fn update(&mut self) {
+        self.img = self.img.translate(Point::new(-DRIFT_SPEED, 0));
+    }
+
+    /// Whether it drifted past the left edge uncollected.
+    pub fn is_gone(&self) -> bool {
+        let bounding_box = self.bounding_box();
+        bounding_box.top_left.x + bounding_box.size.width as i32 <= 0
+    }
+
+    /// The icon and the ring around it.
+    pub fn bounding_box(&self) -> Rectangle {
+        self.img.bounding_box().offset(RING_PADDING as i32)
+    }
+
+    pub fn draw<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let bounding_box = self.bounding_box();
+        Circle::with_center(bounding_box.center(), bounding_box.size.width)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(display)
+            .unwrap();
+        self.img.draw(display).unwrap();
+    }
+}
@@ -0,0 +1,381 @@
+//! Compact input logs for reproducing runs.
+//!
+//! A run is fully determined by the RNG seed it started with and the
+//! [`Input`] of every frame, so that is all a log stores:
+//!
+//! | bytes | content                                         |
+//! |-------|-------------------------------------------------|
+//! | 4     | magic `CYR4`                                    |
+//! | 4     | seed (see [`World::seed`]), little endian       |
+//! | 4 * n | runs of `(x axis, y axis, buttons, frame count)` |
+//!
+//! Frames start with the one that left the menu. The axes are stored as
+//! `i8`, rounded to [`AXIS_STEP`] by the game before it uses them so a stick
+//! that is held still doesn't break runs up. The buttons byte holds fire
+//! presses in bit 0, pause requests in bit 1 and whether the fire button is
+//! held in bit 2. Identical consecutive frames are merged into runs of up to
+//! 255 frames.
+//!
+//! [`World::seed`]: crate::world::World::seed
+
+use heapless::Vec;
+
+use crate::{player::AXIS_MAX, world::Input};
+
+/// Size of the in-memory log, room for 4094 runs. A run ends whenever the
+/// controls change, so a stick that never rests fills the log in under three
+/// minutes (one run per 40 ms frame) while a few changes a second last over a
+/// quarter of an hour. The rest of a longer run isn't recorded, see
+/// [`Recorder::is_truncated`].
+pub const LOG_CAPACITY: usize = 16 * 1024;
+/// Resolution of the recorded axes.
+pub const AXIS_STEP: i8 = AXIS_MAX / 10;
+
+const MAGIC: &[u8; 4] = b"CYR4";
+const HEADER_LEN: usize = 8;
+const RUN_LEN: usize = 4;
+const FIRE_BIT: u8 = 0b001;
+const PAUSE_BIT: u8 = 0b010;
+const FIRE_HELD_BIT: u8 = 0b100;
+
+#[derive(Debug, PartialEq)]
+pub enum ReplayError {
+    /// The log does not start with a valid header.
+    InvalidHeader,
+    /// The log ends in the middle of a run.
+    IncompleteRun,
+    /// A run has an out of range input or zero frames.
+    InvalidRun { offset: usize },
+}
+
+/// Records the input of a run into a fixed-size buffer.
+pub struct Recorder {
+    bytes: Vec<u8, LOG_CAPACITY>,
+    truncated: bool,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    pub const fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// Discards the previous log and starts a new one.
+    pub fn start(&mut self, seed: u32) {
+        self.bytes.clear();
+        self.truncated = false;
+        self.bytes.extend_from_slice(MAGIC).unwrap();
+        self.bytes.extend_from_slice(&seed.to_le_bytes()).unwrap();
+    }
+
+    /// Appends one frame. Ignored until [`start`](Recorder::start) and once
+    /// the buffer is full.
+    pub fn record(&mut self, input: Input) {
+        if self.bytes.len() < HEADER_LEN || self.truncated {
+            return;
+        }
+
+        let [x_axis, y_axis, buttons] = encode(input);
+
+        if self.bytes.len() > HEADER_LEN {
+            if let [.., last_x_axis, last_y_axis, last_buttons, count] = self.bytes.as_mut_slice() {
+                if [*last_x_axis, *last_y_axis, *last_buttons] == [x_axis, y_axis, buttons]
+                    && *count < u8::MAX
+                {
+                    *count += 1;
+                    return;
+                }
+            }
+        }
+
+        if self
+            .bytes
+            .extend_from_slice(&[x_axis, y_axis, buttons, 1])
+            .is_err()
+        {
+            self.truncated = true;
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Whether the run outlasted the buffer. The log is still valid but ends
+    /// before the run did.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+/// Yields the recorded [`Input`] of each frame of a log.
+#[derive(Clone)]
+pub struct Replay<'a> {
+    seed: u32,
+    runs: &'a [u8],
+    truncated: bool,
+    // Frames already replayed from the first run in `runs`
+    replayed: u8,
+}
+
+impl<'a> Replay<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ReplayError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+            return Err(ReplayError::InvalidHeader);
+        }
+
+        let seed = u32::from_le_bytes(bytes[4..HEADER_LEN].try_into().unwrap());
+        let runs = &bytes[HEADER_LEN..];
+
+        if runs.len() % RUN_LEN != 0 {
+            return Err(ReplayError::IncompleteRun);
+        }
+
+        for (index, run) in runs.chunks_exact(RUN_LEN).enumerate() {
+            if decode([run[0], run[1], run[2]]).is_none() || run[3] == 0 {
+                return Err(ReplayError::InvalidRun {
+                    offset: HEADER_LEN + index * RUN_LEN,
+                });
+            }
+        }
+
+        Ok(Self {
+            seed,
+            runs,
+            truncated: bytes.len() + RUN_LEN > LOG_CAPACITY,
+            replayed: 0,
+        })
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Whether the log filled a [`Recorder`], so the run likely went on past
+    /// its end.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Iterator for Replay<'_> {
+    type Item = Input;
+
+    fn next(&mut self) -> Option<Input> {
+        let [x_axis, y_axis, buttons, count, ..] = *self.runs else {
+            return None;
+        };
+
+        self.replayed += 1;
+        if self.replayed == count {
+            self.runs = &self.runs[RUN_LEN..];
+            self.replayed = 0;
+        }
+
+        decode([x_axis, y_axis, buttons])
+    }
+}
+
+/// Rounds `axis` to the nearest multiple of [`AXIS_STEP`].
+pub fn quantize_axis(axis: i8) -> i8 {
+    let half_step = AXIS_STEP / 2 * axis.signum();
+    (axis.saturating_add(half_step) / AXIS_STEP) * AXIS_STEP
+}
+
+fn encode(input: Input) -> [u8; 3] {
+    let mut buttons = 0;
+    if input.fire {
+        buttons |= FIRE_BIT;
+    }
+    if input.pause {
+        buttons |= PAUSE_BIT;
+    }
+    if input.fire_held {
+        buttons |= FIRE_HELD_BIT;
+    }
+    [input.x_axis as u8, input.y_axis as u8, buttons]
+}
+
+fn decode([x_axis, y_axis, buttons]: [u8; 3]) -> Option<Input> {
+    let axis_range = -AXIS_MAX..=AXIS_MAX;
+    let (x_axis, y_axis) = (x_axis as i8, y_axis as i8);
+    let unknown_buttons = buttons & !(FIRE_BIT | PAUSE_BIT | FIRE_HELD_BIT);
+    if !axis_range.contains(&x_axis) || !axis_range.contains(&y_axis) || unknown_buttons != 0 {
+        return None;
+    }
+
+    Some(Input {
+        x_axis,
+        y_axis,
+        fire: buttons & FIRE_BIT != 0,
+        fire_held: buttons & FIRE_HELD_BIT != 0,
+        pause: buttons & PAUSE_BIT != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rng::XorShift32,
+        world::{GameState, World},
+    };
+
+    const IDLE: Input = Input {
+        x_axis: 0,
+        y_axis: 0,
+        fire: false,
+        fire_held: false,
+        pause: false,
+    };
+
+    /// Varied input for `frame`, changing every few frames.
+    fn input(frame: u32) -> Input {
+        Input {
+            x_axis: ((frame / 5 % 21) as i32 * AXIS_STEP as i32 - AXIS_MAX as i32) as i8,
+            y_axis: if frame / 40 % 2 == 0 {
+                AXIS_MAX
+            } else {
+                -AXIS_MAX
+            },
+            fire: frame % 3 == 0,
+            fire_held: frame / 10 % 2 == 0,
+            pause: false,
+        }
+    }
+
+    fn started_recorder() -> Recorder {
+        let mut recorder = Recorder::new();
+        recorder.start(0x1234_5678);
+        recorder
+    }
+
+    #[test]
+    fn recorded_inputs_replay_in_order() {
+        let mut recorder = started_recorder();
+        let inputs: Vec<Input, 200> = (0..200).map(input).collect();
+        for &input in &inputs {
+            recorder.record(input);
+        }
+
+        let replay = Replay::parse(recorder.as_bytes()).unwrap();
+        assert_eq!(replay.seed(), 0x1234_5678);
+        assert!(!replay.is_truncated());
+        assert!(replay.eq(inputs.iter().copied()));
+    }
+
+    #[test]
+    fn runs_split_after_255_frames() {
+        let mut recorder = started_recorder();
+        for _ in 0..300 {
+            recorder.record(IDLE);
+        }
+
+        let runs = &recorder.as_bytes()[HEADER_LEN..];
+        assert_eq!(runs, [0, 0, 0, 255, 0, 0, 0, 45]);
+        assert_eq!(Replay::parse(recorder.as_bytes()).unwrap().count(), 300);
+    }
+
+    #[test]
+    fn full_buffer_truncates_the_log() {
+        let mut recorder = started_recorder();
+        let mut frame = 0;
+        while !recorder.is_truncated() {
+            recorder.record(Input {
+                fire: frame % 2 == 0,
+                ..IDLE
+            });
+            frame += 1;
+        }
+
+        assert_eq!(frame, (LOG_CAPACITY - HEADER_LEN) / RUN_LEN + 1);
+        let replay = Replay::parse(recorder.as_bytes()).unwrap();
+        assert!(replay.is_truncated());
+        assert_eq!(replay.count(), frame - 1);
+    }
+
+    #[test]
+    fn invalid_header_is_rejected() {
+        let mut bytes = *b"CYR4\0\0\0\0";
+        assert_eq!(
+            Replay::parse(&bytes[..7]).err(),
+            Some(ReplayError::InvalidHeader)
+        );
+        bytes[3] = b'3';
+        assert_eq!(
+            Replay::parse(&bytes).err(),
+            Some(ReplayError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn partial_run_is_rejected() {
+        let mut recorder = started_recorder();
+        recorder.record(IDLE);
+        let bytes = recorder.as_bytes();
+
+        assert_eq!(
+            Replay::parse(&bytes[..bytes.len() - 1]).err(),
+            Some(ReplayError::IncompleteRun)
+        );
+    }
+
+    #[test]
+    fn invalid_runs_are_rejected() {
+        let header = *b"CYR4\0\0\0\0";
+        let log = |run: [u8; 8]| {
+            let mut bytes: Vec<u8, 16> = Vec::new();
+            bytes.extend_from_slice(&header).unwrap();
+            bytes.extend_from_slice(&run).unwrap();
+            bytes
+        };
+        let invalid_at_second_run = Some(ReplayError::InvalidRun { offset: 12 });
+
+        // Zero frames, an axis past AXIS_MAX and an unknown button
+        for run in [
+            [0, 0, 0, 1, 0, 0, 0, 0],
+            [0, 0, 0, 1, AXIS_MAX as u8 + 1, 0, 0, 1],
+            [0, 0, 0, 1, 0, 0, 0b1000, 1],
+        ] {
+            assert_eq!(Replay::parse(&log(run)).err(), invalid_at_second_run);
+        }
+    }
+
+    #[test]
+    fn replayed_log_reproduces_the_run() {
+        let mut world = World::new(128, 64, XorShift32::new(99));
+        let mut recorder = Recorder::new();
+
+        let start = Input { fire: true, ..IDLE };
+        world.step(start);
+        recorder.start(world.seed());
+        recorder.record(start);
+        for frame in 0..3000 {
+            if world.state() == GameState::Dead {
+                break;
+            }
+            world.step(input(frame));
+            recorder.record(input(frame));
+        }
+
+        let replay = Replay::parse(recorder.as_bytes()).unwrap();
+        let mut replayed = World::new(128, 64, XorShift32::new(replay.seed()));
+        for input in replay {
+            replayed.step(input);
+        }
+
+        assert!(world.score() > 0);
+        assert_eq!(replayed.score(), world.score());
+        assert_eq!(replayed.level(), world.level());
+        assert_eq!(replayed.lives(), world.lives());
+        assert_eq!(replayed.state(), world.state());
+    }
+}
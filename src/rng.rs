@@ -0,0 +1,80 @@
+//! Random numbers for gameplay.
+//!
+//! Gameplay draws from a seedable software generator instead of the hardware
+//! RNG so a run can be replayed exactly from its seed, on target or on the
+//! host. The hardware RNG is only used to pick the seed.
+
+/// A source of random numbers.
+pub trait GameRng {
+    fn next_u32(&mut self) -> u32;
+}
+
+#[cfg(feature = "esp32")]
+impl GameRng for esp_hal::rng::Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.random()
+    }
+}
+
+// Any non-zero value works, xorshift only has to avoid the all-zero state
+const ZERO_SEED_REPLACEMENT: u32 = 0x2545_f491;
+
+/// Marsaglia's xorshift32. The same seed gives the same sequence everywhere.
+#[derive(Clone)]
+pub struct XorShift32 {
+    state: u32,
+}
+
+impl XorShift32 {
+    pub fn new(seed: u32) -> Self {
+        let state = if seed == 0 {
+            ZERO_SEED_REPLACEMENT
+        } else {
+            seed
+        };
+        Self { state }
+    }
+
+    /// Seeds a new generator from another source, e.g. the hardware RNG.
+    pub fn from_rng(rng: &mut impl GameRng) -> Self {
+        Self::new(rng.next_u32())
+    }
+
+    /// The current state; passing it to [`XorShift32::new`] continues the
+    /// sequence from here.
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+}
+
+impl GameRng for XorShift32 {
+    fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_matches_xorshift32() {
+        let mut rng = XorShift32::new(1);
+        let values = [(); 4].map(|_| rng.next_u32());
+
+        assert_eq!(values, [270_369, 67_634_689, 2_647_435_461, 307_599_695]);
+        assert_eq!(rng.state(), 307_599_695);
+    }
+
+    #[test]
+    fn zero_seed_is_replaced() {
+        let mut rng = XorShift32::new(0);
+        assert_eq!(rng.state(), ZERO_SEED_REPLACEMENT);
+        assert_eq!(rng.next_u32(), 0xe124_b63a);
+    }
+}
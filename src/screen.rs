@@ -0,0 +1,555 @@
+//! What the game shows, one [`Screen`] per state.
+//!
+//! Every frame [`Game`](crate::game::Game) clears the framebuffer, renders
+//! the screen of the current state into it and flushes it once.
+
+use core::fmt::{Debug, Write};
+
+use embedded_graphics::{
+    image::Image,
+    mono_font::{
+        ascii::{FONT_4X6, FONT_6X10, FONT_9X18_BOLD},
+        MonoTextStyleBuilder,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle},
+    text::{Baseline, Text},
+};
+use heapless::String;
+
+#[cfg(feature = "debug")]
+use crate::timing::{FrameStats, FRAME_TIME};
+use crate::{
+    audio,
+    highscore::{HighScores, InitialsEntry, INITIALS_LEN},
+    powerup::PowerUpKind,
+    sprites,
+    world::{PauseOption, World},
+};
+
+// HUD icons of power-up effects blink once this close to running out
+const EFFECT_BLINK_FRAMES: u32 = 90;
+
+pub trait Screen {
+    /// Draws the screen onto a cleared `display`.
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug;
+}
+
+/// The title, shown in the menu.
+pub struct TitleScreen;
+
+impl Screen for TitleScreen {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        Image::new(&sprites::RAW_BOW_ARROW, Point::new(16, 0))
+            .draw(display)
+            .unwrap();
+
+        let tile1 = "COSMIC";
+        let title2 = "YUDH";
+
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_9X18_BOLD)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let x = sprites::RAW_BOW_ARROW.size().width as i32 + 30;
+        Text::with_baseline(tile1, Point::new(x, 15), text_style, Baseline::Top)
+            .draw(display)
+            .unwrap();
+
+        Text::with_baseline(title2, Point::new(x + 3, 35), text_style, Baseline::Top)
+            .draw(display)
+            .unwrap();
+
+        draw_universe(display);
+    }
+}
+
+/// The playfield and the HUD.
+pub struct PlayScreen<'a> {
+    pub world: &'a World,
+    /// Where the playfield is moved by the screen shake.
+    pub offset: Point,
+}
+
+impl Screen for PlayScreen<'_> {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let mut playfield = display.translated(self.offset);
+
+        self.world.player.draw(&mut playfield);
+        self.world.enemy.draw(&mut playfield);
+        if let Some(power_up) = self.world.power_up.as_ref() {
+            power_up.draw(&mut playfield);
+        }
+        self.print_score(display);
+        self.print_level(display);
+        self.print_lives(display);
+        self.print_effects(display);
+        draw_universe(display);
+    }
+}
+
+impl PlayScreen<'_> {
+    fn print_score<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let mut score_text: String<16> = String::new();
+        write!(score_text, "Score: {}", self.world.score()).unwrap();
+
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let text_width = score_text.len() as i32 * FONT_6X10.character_size.width as i32;
+        let text_height = FONT_6X10.character_size.height as i32;
+
+        let Size { width, height } = display.bounding_box().size;
+
+        // Calculate top-left position to center the text
+        let x = (width as i32 - text_width) / 2;
+        let y = height as i32 - text_height;
+
+        Text::with_baseline(&score_text, Point::new(x, y), text_style, Baseline::Top)
+            .draw(display)
+            .unwrap();
+    }
+
+    fn print_level<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let mut score_text: String<16> = String::new();
+        write!(score_text, "L: {}", self.world.level()).unwrap();
+
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let x = 60;
+        let y = 0;
+
+        Text::with_baseline(&score_text, Point::new(x, y), text_style, Baseline::Top)
+            .draw(display)
+            .unwrap();
+    }
+
+    fn print_lives<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let img_width: i32 = sprites::RAW_HEART.bounding_box().size.width as i32;
+
+        let x = 28;
+
+        for i in 0..self.world.lives() {
+            let image = Image::new(
+                &sprites::RAW_HEART,
+                Point::new(x + (i as i32 * img_width), 0),
+            );
+            image.draw(display).unwrap();
+        }
+    }
+
+    /// Icons of the active power-up effects, left of the lives.
+    fn print_effects<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let mut x = 0;
+
+        for kind in PowerUpKind::ALL {
+            let frames = self.world.player.effect_frames(kind);
+            if frames == 0 {
+                continue;
+            }
+
+            let icon = kind.icon();
+            let blink_off = frames < EFFECT_BLINK_FRAMES && frames / 8 % 2 == 1;
+            if !blink_off {
+                Image::new(icon, Point::new(x, 1)).draw(display).unwrap();
+            }
+            x += icon.size().width as i32 + 1;
+        }
+    }
+}
+
+/// A box over the frozen game with the pause options, the selected one
+/// marked with an arrow.
+pub struct PauseScreen<'a> {
+    pub game: PlayScreen<'a>,
+}
+
+impl Screen for PauseScreen<'_> {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        self.game.render(display);
+
+        let style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+        let line_height = FONT_6X10.character_size.height as i32;
+
+        let width = display.bounding_box().size.width as i32;
+        let menu = Rectangle::new(Point::new(24, 4), Size::new(width as u32 - 48, 56));
+        menu.into_styled(
+            PrimitiveStyleBuilder::new()
+                .fill_color(BinaryColor::Off)
+                .stroke_color(BinaryColor::On)
+                .stroke_width(1)
+                .build(),
+        )
+        .draw(display)
+        .unwrap();
+
+        let title = "PAUSED";
+        let title_width = title.len() as i32 * FONT_6X10.character_size.width as i32;
+        Text::with_baseline(
+            title,
+            Point::new((width - title_width) / 2, 6),
+            style,
+            Baseline::Top,
+        )
+        .draw(display)
+        .unwrap();
+
+        let x = menu.top_left.x + 4;
+        let selected = self.game.world.pause_selection();
+        for (i, option) in PauseOption::ALL.into_iter().enumerate() {
+            let label = match option {
+                PauseOption::Resume => "Resume",
+                PauseOption::Restart => "Restart",
+                PauseOption::Sound if audio::is_enabled() => "Sound: on",
+                PauseOption::Sound => "Sound: off",
+                PauseOption::MainMenu => "Main menu",
+            };
+
+            let mut line: String<16> = String::new();
+            let marker = if option == selected { '>' } else { ' ' };
+            write!(line, "{marker}{label}").unwrap();
+
+            let y = 18 + i as i32 * line_height;
+            Text::with_baseline(&line, Point::new(x, y), style, Baseline::Top)
+                .draw(display)
+                .unwrap();
+        }
+    }
+}
+
+/// The intermission between levels, with the stats of the one just cleared.
+pub struct LevelCompletedScreen<'a> {
+    pub world: &'a World,
+}
+
+impl Screen for LevelCompletedScreen<'_> {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let width = display.bounding_box().size.width as i32;
+
+        let mut title: String<16> = String::new();
+        write!(title, "LEVEL {} CLEAR", self.world.level()).unwrap();
+        let title_style = MonoTextStyleBuilder::new()
+            .font(&FONT_9X18_BOLD)
+            .text_color(BinaryColor::On)
+            .build();
+        let title_width = title.len() as i32 * FONT_9X18_BOLD.character_size.width as i32;
+        Text::with_baseline(
+            &title,
+            Point::new((width - title_width) / 2, 2),
+            title_style,
+            Baseline::Top,
+        )
+        .draw(display)
+        .unwrap();
+
+        let stats = self.world.stats();
+        let mut lines: [String<24>; 3] = [String::new(), String::new(), String::new()];
+        write!(lines[0], "Shots: {}", stats.shots).unwrap();
+        write!(lines[1], "Accuracy: {}%", stats.accuracy()).unwrap();
+        write!(lines[2], "Lives: {}", self.world.lives()).unwrap();
+
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+        for (line, y) in lines.iter().zip([26, 38, 50]) {
+            let text_width = line.len() as i32 * FONT_6X10.character_size.width as i32;
+            Text::with_baseline(
+                line,
+                Point::new((width - text_width) / 2, y),
+                text_style,
+                Baseline::Top,
+            )
+            .draw(display)
+            .unwrap();
+        }
+    }
+}
+
+/// Shown once the player runs out of lives.
+pub struct GameOverScreen {
+    pub score: u32,
+}
+
+impl Screen for GameOverScreen {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let mut score_text: String<32> = String::new();
+
+        Image::new(&sprites::RAW_GAME_OVER, Point::new(16, 28))
+            .draw(display)
+            .unwrap();
+
+        write!(score_text, "Score: {}", self.score).unwrap();
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+
+        // Centred horizontally
+        let text_width = score_text.len() as i32 * FONT_6X10.character_size.width as i32;
+        let width = display.bounding_box().size.width;
+        let x = (width as i32 - text_width) / 2;
+
+        Text::with_baseline(&score_text, Point::new(x, 42), text_style, Baseline::Top)
+            .draw(display)
+            .unwrap();
+    }
+}
+
+/// The player picks their initials after a high score.
+pub struct InitialsScreen<'a> {
+    pub entry: &'a InitialsEntry,
+    pub score: u32,
+}
+
+impl Screen for InitialsScreen<'_> {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let initials = *self.entry.initials();
+        let cursor = self.entry.cursor();
+
+        let width = display.bounding_box().size.width as i32;
+
+        let small_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+        let large_style = MonoTextStyleBuilder::new()
+            .font(&FONT_9X18_BOLD)
+            .text_color(BinaryColor::On)
+            .build();
+
+        let mut lines: [String<24>; 2] = [String::new(), String::new()];
+        write!(lines[0], "NEW HIGH SCORE").unwrap();
+        write!(lines[1], "Score: {}", self.score).unwrap();
+        for (line, y) in lines.iter().zip([2, 14]) {
+            let text_width = line.len() as i32 * FONT_6X10.character_size.width as i32;
+            let x = (width - text_width) / 2;
+            Text::with_baseline(line, Point::new(x, y), small_style, Baseline::Top)
+                .draw(display)
+                .unwrap();
+        }
+
+        // Letters are spread out so the cursor under one is easy to spot
+        let spacing = 16;
+        let letter_width = FONT_9X18_BOLD.character_size.width as i32;
+        let left = (width - spacing * (INITIALS_LEN as i32 - 1) - letter_width) / 2;
+        let mut letter = [0; 4];
+        for (i, initial) in initials.iter().enumerate() {
+            let x = left + i as i32 * spacing;
+            let text = char::from(*initial).encode_utf8(&mut letter);
+            Text::with_baseline(text, Point::new(x, 30), large_style, Baseline::Top)
+                .draw(display)
+                .unwrap();
+
+            if i == cursor {
+                Line::new(Point::new(x, 50), Point::new(x + letter_width - 1, 50))
+                    .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 2))
+                    .draw(display)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// The table in two columns, with the entry at `new_rank` highlighted.
+pub struct HighScoresScreen<'a> {
+    pub high_scores: &'a HighScores,
+    pub new_rank: usize,
+}
+
+impl Screen for HighScoresScreen<'_> {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_6X10)
+            .text_color(BinaryColor::On)
+            .build();
+        let entry_style = MonoTextStyleBuilder::new()
+            .font(&FONT_4X6)
+            .text_color(BinaryColor::On)
+            .build();
+        let highlight_style = MonoTextStyleBuilder::new()
+            .font(&FONT_4X6)
+            .text_color(BinaryColor::Off)
+            .background_color(BinaryColor::On)
+            .build();
+
+        let title = "HIGH SCORES";
+        let width = display.bounding_box().size.width as i32;
+        let text_width = title.len() as i32 * FONT_6X10.character_size.width as i32;
+        Text::with_baseline(
+            title,
+            Point::new((width - text_width) / 2, 0),
+            text_style,
+            Baseline::Top,
+        )
+        .draw(display)
+        .unwrap();
+
+        let rows = self.high_scores.entries().len().div_ceil(2).max(1);
+        for (rank, entry) in self.high_scores.entries().iter().enumerate() {
+            let mut line: String<24> = String::new();
+            let initials = core::str::from_utf8(&entry.initials).unwrap_or("???");
+            write!(
+                line,
+                "{:>2} {} {:>4} L{}",
+                rank + 1,
+                initials,
+                entry.score,
+                entry.level
+            )
+            .unwrap();
+
+            let x = 2 + (rank / rows) as i32 * width / 2;
+            let y = 14 + (rank % rows) as i32 * 10;
+            let style = if rank == self.new_rank {
+                highlight_style
+            } else {
+                entry_style
+            };
+            Text::with_baseline(&line, Point::new(x, y), style, Baseline::Top)
+                .draw(display)
+                .unwrap();
+        }
+    }
+}
+
+/// Frame rate and longest frame time of the last second, at the top right
+/// over any screen. Inverted while frames go over budget.
+#[cfg(feature = "debug")]
+pub struct FrameStatsOverlay<'a> {
+    pub stats: &'a FrameStats,
+}
+
+#[cfg(feature = "debug")]
+impl Screen for FrameStatsOverlay<'_> {
+    fn render<D>(&self, display: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+        D::Error: Debug,
+    {
+        let mut stats_text: String<16> = String::new();
+        write!(
+            stats_text,
+            "{}f {}ms",
+            self.stats.fps(),
+            self.stats.worst_frame().as_millis()
+        )
+        .unwrap();
+
+        let over_budget = self.stats.worst_frame() > FRAME_TIME;
+        let (text_color, background_color) = if over_budget {
+            (BinaryColor::Off, BinaryColor::On)
+        } else {
+            (BinaryColor::On, BinaryColor::Off)
+        };
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_4X6)
+            .text_color(text_color)
+            .background_color(background_color)
+            .build();
+
+        let text_width = stats_text.len() as i32 * FONT_4X6.character_size.width as i32;
+        let x = display.bounding_box().size.width as i32 - text_width;
+
+        Text::with_baseline(&stats_text, Point::new(x, 0), text_style, Baseline::Top)
+            .draw(display)
+            .unwrap();
+    }
+}
+
+fn draw_universe<D>(display: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+    D::Error: Debug,
+{
+    let stars = [
+        // Star size : 1
+        (10, 10, 1),
+        (30, 5, 1),
+        (50, 25, 1),
+        (80, 10, 1),
+        (100, 30, 1),
+        (110, 5, 1),
+        (60, 40, 1),
+        (15, 25, 1),
+        (25, 35, 1),
+        (35, 45, 1),
+        (55, 60, 1),
+        (65, 20, 1),
+        (75, 30, 1),
+        (85, 40, 1),
+        (105, 60, 1),
+        (115, 15, 1),
+        // Star size: 2
+        (5, 50, 2),
+        (20, 15, 2),
+        (25, 58, 2),
+        (90, 20, 2),
+        (95, 50, 2),
+        (123, 25, 2),
+    ];
+
+    for (x, y, size) in stars {
+        Circle::new(Point::new(x, y), size)
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(display)
+            .unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+//! Host-side stand-in for the OLED used by [`Game`](crate::game::Game).
+//!
+//! See `src/bin/sim.rs` for the binary driving it.
+
+use core::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+use crate::display::Flush;
+
+pub const WIDTH: u32 = 128;
+pub const HEIGHT: u32 = 64;
+const BUFFER_SIZE: usize = (WIDTH * HEIGHT / 8) as usize;
+
+/// In-memory 128x64 monochrome framebuffer.
+///
+/// Every [`flush`](Flush::flush) hands the current frame to the
+/// `present` callback, which the simulator uses to dump it to a file or the
+/// terminal.
+pub struct SimDisplay {
+    buffer: [u8; BUFFER_SIZE],
+    present: fn(&SimDisplay),
+}
+
+impl SimDisplay {
+    pub fn new(present: fn(&SimDisplay)) -> Self {
+        Self {
+            buffer: [0; BUFFER_SIZE],
+            present,
+        }
+    }
+
+    /// Returns whether the pixel at `(x, y)` is lit. Out of range is off.
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        if x >= WIDTH || y >= HEIGHT {
+            return false;
+        }
+        let index = (y * WIDTH + x) as usize;
+        self.buffer[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+
+    /// Raw frame, one bit per pixel, rows packed MSB first (PBM P4 layout).
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl OriginDimensions for SimDisplay {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl DrawTarget for SimDisplay {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y)) else {
+                continue;
+            };
+            if x >= WIDTH || y >= HEIGHT {
+                continue;
+            }
+
+            let index = (y * WIDTH + x) as usize;
+            let mask = 0x80 >> (index % 8);
+            if color.is_on() {
+                self.buffer[index / 8] |= mask;
+            } else {
+                self.buffer[index / 8] &= !mask;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Flush for SimDisplay {
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        (self.present)(self);
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 use embedded_graphics::{image::ImageRaw, pixelcolor::BinaryColor};
 
+use crate::collision::Mask;
+
 pub type ImgRawType = ImageRaw<'static, BinaryColor>;
 
 // 'player-jet', WxH Pixel = 16 x 16 px
@@ -8,6 +10,7 @@ const SPRITE_PLAYER_JET: [u8; 32] = [
     0x7F, 0xFF, 0xFF, 0x00, 0x3C, 0x00, 0x3C, 0x00, 0x1F, 0xF0, 0x1C, 0x00, 0x00, 0x00, 0x00, 0x00,
 ];
 pub const RAW_PLAYER_JET: ImgRawType = ImageRaw::new(&SPRITE_PLAYER_JET, 16);
+pub const MASK_PLAYER_JET: Mask = Mask::new(&SPRITE_PLAYER_JET, 16);
 
 /// 'planet-killer', WxH Pixel = 24 x 24 px
 const SPRITE_PLANET_KILLER: [u8; 72] = [
@@ -19,10 +22,65 @@ const SPRITE_PLANET_KILLER: [u8; 72] = [
 ];
 
 pub const RAW_PLANET_KILLER: ImgRawType = ImageRaw::new(&SPRITE_PLANET_KILLER, 24);
+pub const MASK_PLANET_KILLER: Mask = Mask::new(&SPRITE_PLANET_KILLER, 24);
+
+// 'fighter', WxH Pixel = 16 x 16 px
+const SPRITE_FIGHTER: [u8; 32] = [
+    0x00, 0x00, 0x00, 0x30, 0x00, 0x78, 0x00, 0xcc, 0x01, 0x80, 0x1f, 0xf8, 0x7f, 0xfe, 0xff, 0xff,
+    0xff, 0xff, 0x7f, 0xfe, 0x1f, 0xf8, 0x01, 0x80, 0x00, 0xcc, 0x00, 0x78, 0x00, 0x30, 0x00, 0x00,
+];
+pub const RAW_FIGHTER: ImgRawType = ImageRaw::new(&SPRITE_FIGHTER, 16);
+pub const MASK_FIGHTER: Mask = Mask::new(&SPRITE_FIGHTER, 16);
+
+// 'kamikaze', WxH Pixel = 16 x 8 px
+const SPRITE_KAMIKAZE: [u8; 16] = [
+    0x00, 0x3f, 0x01, 0xf0, 0x0f, 0xf8, 0xff, 0xff, 0xff, 0xff, 0x0f, 0xf8, 0x01, 0xf0, 0x00, 0x3f,
+];
+pub const RAW_KAMIKAZE: ImgRawType = ImageRaw::new(&SPRITE_KAMIKAZE, 16);
+pub const MASK_KAMIKAZE: Mask = Mask::new(&SPRITE_KAMIKAZE, 16);
+
+// 'turret', WxH Pixel = 16 x 16 px
+const SPRITE_TURRET: [u8; 32] = [
+    0x00, 0x00, 0x01, 0xf8, 0x07, 0xfe, 0x0f, 0xfe, 0x0f, 0x3e, 0x0e, 0x1e, 0xff, 0xde, 0xff, 0xde,
+    0x0e, 0x1e, 0x0f, 0x3e, 0x0f, 0xfe, 0x07, 0xfe, 0x01, 0xf8, 0x0f, 0xff, 0x1f, 0xff, 0x1f, 0xff,
+];
+pub const RAW_TURRET: ImgRawType = ImageRaw::new(&SPRITE_TURRET, 16);
+pub const MASK_TURRET: Mask = Mask::new(&SPRITE_TURRET, 16);
+
+// 'boss-core', WxH Pixel = 32 x 24 px
+const SPRITE_BOSS_CORE: [u8; 96] = [
+    0x00, 0x3f, 0xf0, 0x00, 0x01, 0xff, 0xfe, 0x00, 0x07, 0xff, 0xff, 0x80, 0x0f, 0xff, 0xff, 0xc0,
+    0x1f, 0xff, 0xff, 0xe0, 0x3e, 0x3f, 0xf8, 0xf0, 0x3c, 0x1f, 0xf0, 0x78, 0x7c, 0x9f, 0xf2, 0x7c,
+    0x7e, 0x3f, 0xf8, 0xfc, 0x7f, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xfe, 0x7f, 0xfe, 0x7f,
+    0xfe, 0x7f, 0xfe, 0x7f, 0xff, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xfe, 0x7e, 0x3f, 0xf8, 0xfc,
+    0x7c, 0x9f, 0xf2, 0x7c, 0x3c, 0x1f, 0xf0, 0x78, 0x3e, 0x3f, 0xf8, 0xf0, 0x1f, 0xff, 0xff, 0xe0,
+    0x0f, 0xff, 0xff, 0xc0, 0x07, 0xff, 0xff, 0x80, 0x01, 0xff, 0xfe, 0x00, 0x00, 0x3f, 0xf0, 0x00,
+];
+pub const RAW_BOSS_CORE: ImgRawType = ImageRaw::new(&SPRITE_BOSS_CORE, 32);
+pub const MASK_BOSS_CORE: Mask = Mask::new(&SPRITE_BOSS_CORE, 32);
+
+// 'boss-cannon', WxH Pixel = 16 x 6 px
+const SPRITE_BOSS_CANNON: [u8; 12] = [
+    0x03, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0x03, 0xff,
+];
+pub const RAW_BOSS_CANNON: ImgRawType = ImageRaw::new(&SPRITE_BOSS_CANNON, 16);
+pub const MASK_BOSS_CANNON: Mask = Mask::new(&SPRITE_BOSS_CANNON, 16);
 
 const HEART_SPRITE: [u8; 8] = [0x00, 0x6e, 0xff, 0xef, 0x7e, 0x3c, 0x18, 0x00];
 pub const RAW_HEART: ImgRawType = ImageRaw::<BinaryColor>::new(&HEART_SPRITE, 8);
 
+// 'shield', WxH Pixel = 8 x 8 px
+const SPRITE_SHIELD: [u8; 8] = [0x7e, 0x81, 0xbd, 0xbd, 0xbd, 0x5a, 0x24, 0x18];
+pub const RAW_SHIELD: ImgRawType = ImageRaw::new(&SPRITE_SHIELD, 8);
+
+// 'rapid-fire', WxH Pixel = 8 x 8 px
+const SPRITE_RAPID_FIRE: [u8; 8] = [0x88, 0xcc, 0x66, 0x33, 0x33, 0x66, 0xcc, 0x88];
+pub const RAW_RAPID_FIRE: ImgRawType = ImageRaw::new(&SPRITE_RAPID_FIRE, 8);
+
+// 'spread', WxH Pixel = 8 x 8 px
+const SPRITE_SPREAD: [u8; 8] = [0x07, 0x18, 0x60, 0xff, 0x60, 0x18, 0x07, 0x00];
+pub const RAW_SPREAD: ImgRawType = ImageRaw::new(&SPRITE_SPREAD, 8);
+
 // 'game-over', WxH Pixel = 100 x 7 px
 const SPRITE_GAME_OVER: [u8; 91] = [
     0x7c, 0x03, 0x80, 0x66, 0x07, 0xf0, 0x00, 0x7c, 0x06, 0x60, 0x7f, 0x03, 0xe0, 0x7c, 0x07, 0xc0,
@@ -0,0 +1,192 @@
+//! Fixed-timestep scheduling of the game loop.
+//!
+//! The world advances in steps of [`FRAME_TIME`] whatever drawing and
+//! flushing the display cost, so the game runs at the same speed on a slow
+//! I2C bus. After a slow frame the loop runs the updates it missed before
+//! drawing again, up to [`MAX_CATCH_UP`] of them; past that the game slows
+//! down rather than jumping ahead. Updates put back with
+//! [`FrameClock::defer`], e.g. to draw a change of state first, are run in
+//! the next frame instead.
+
+use embassy_time::{Duration, Instant, Timer};
+use log::warn;
+
+/// Game time advanced by one update.
+pub const FRAME_TIME: Duration = Duration::from_millis(40);
+/// Most updates run between two draws to catch up.
+pub const MAX_CATCH_UP: u32 = 4;
+// Frame stats are gathered over windows this long
+const STATS_WINDOW: Duration = Duration::from_secs(1);
+
+/// Keeps the updates of the game loop on a fixed schedule.
+pub struct FrameClock {
+    next_tick: Instant,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameClock {
+    /// Starts the schedule with an update due right away.
+    pub fn new() -> Self {
+        Self {
+            next_tick: Instant::now(),
+        }
+    }
+
+    /// Number of updates due by now, at most [`MAX_CATCH_UP`]. Updates
+    /// missed past that are dropped.
+    pub fn due_updates(&mut self) -> u32 {
+        self.due_updates_at(Instant::now())
+    }
+
+    fn due_updates_at(&mut self, now: Instant) -> u32 {
+        let mut updates = 0;
+        while self.next_tick <= now && updates < MAX_CATCH_UP {
+            self.next_tick += FRAME_TIME;
+            updates += 1;
+        }
+        if self.next_tick <= now {
+            self.next_tick = now + FRAME_TIME;
+        }
+
+        updates
+    }
+
+    /// Puts back `updates` of those returned by
+    /// [`due_updates`](Self::due_updates) that weren't run, so the next call
+    /// returns them again.
+    pub fn defer(&mut self, updates: u32) {
+        self.next_tick -= FRAME_TIME * updates;
+    }
+
+    /// Waits until the next update is due.
+    pub async fn wait(&self) {
+        Timer::at(self.next_tick).await;
+    }
+
+    /// Starts the schedule over from now, so time spent outside the loop
+    /// isn't caught up.
+    pub fn restart(&mut self) {
+        self.next_tick = Instant::now();
+    }
+}
+
+/// How long frames take to update, draw and flush, against the
+/// [`FRAME_TIME`] budget.
+pub struct FrameStats {
+    window_start: Instant,
+    frames: u32,
+    longest: Duration,
+    // Results of the last full window
+    fps: u32,
+    worst_frame: Duration,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameStats {
+    pub const fn new() -> Self {
+        Self {
+            window_start: Instant::from_ticks(0),
+            frames: 0,
+            longest: Duration::from_ticks(0),
+            fps: 0,
+            worst_frame: Duration::from_ticks(0),
+        }
+    }
+
+    /// Counts a frame that started at `start` and is done now. Logs a
+    /// warning once per window if frames went over budget.
+    pub fn record(&mut self, start: Instant) {
+        let now = Instant::now();
+        self.frames += 1;
+        self.longest = self.longest.max(now - start);
+
+        if now - self.window_start < STATS_WINDOW {
+            return;
+        }
+
+        self.fps = self.frames;
+        self.worst_frame = self.longest;
+        if self.worst_frame > FRAME_TIME {
+            warn!(
+                "Frame took {} ms, over the {} ms budget",
+                self.worst_frame.as_millis(),
+                FRAME_TIME.as_millis()
+            );
+        }
+
+        self.window_start = now;
+        self.frames = 0;
+        self.longest = Duration::from_ticks(0);
+    }
+
+    /// Frames drawn during the last full second.
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
+    /// Longest frame of the last full second.
+    pub fn worst_frame(&self) -> Duration {
+        self.worst_frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock_due_at(ms: u64) -> FrameClock {
+        FrameClock {
+            next_tick: Instant::from_millis(ms),
+        }
+    }
+
+    #[test]
+    fn updates_follow_the_frame_time() {
+        let mut clock = clock_due_at(1000);
+
+        assert_eq!(clock.due_updates_at(Instant::from_millis(999)), 0);
+        assert_eq!(clock.due_updates_at(Instant::from_millis(1000)), 1);
+        assert_eq!(clock.due_updates_at(Instant::from_millis(1039)), 0);
+        assert_eq!(clock.due_updates_at(Instant::from_millis(1125)), 3);
+        assert_eq!(clock.next_tick, Instant::from_millis(1160));
+    }
+
+    #[test]
+    fn catch_up_is_capped() {
+        let mut clock = clock_due_at(1000);
+
+        let now = Instant::from_millis(1000) + FRAME_TIME * (MAX_CATCH_UP - 1);
+        assert_eq!(clock.due_updates_at(now), MAX_CATCH_UP);
+        assert_eq!(clock.due_updates_at(now), 0);
+    }
+
+    #[test]
+    fn long_stall_restarts_the_schedule() {
+        let mut clock = clock_due_at(1000);
+
+        let now = Instant::from_millis(6000);
+        assert_eq!(clock.due_updates_at(now), MAX_CATCH_UP);
+        assert_eq!(clock.next_tick, now + FRAME_TIME);
+        assert_eq!(clock.due_updates_at(now + FRAME_TIME), 1);
+    }
+
+    #[test]
+    fn deferred_updates_are_due_again() {
+        let mut clock = clock_due_at(1000);
+        let now = Instant::from_millis(1100);
+
+        assert_eq!(clock.due_updates_at(now), 3);
+        clock.defer(2);
+        assert_eq!(clock.due_updates_at(now), 2);
+    }
+}
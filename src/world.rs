@@ -0,0 +1,635 @@
+//! Game rules, free of rendering, timing and hardware.
+//!
+//! [`World::step`] advances the game by one frame from an [`Input`] snapshot
+//! and reports what happened as [`Event`]s. The async loop in
+//! [`Game`](crate::game::Game) only feeds it input, renders it and plays the
+//! sounds for the returned events.
+
+use embedded_graphics::prelude::*;
+use heapless::{spsc::Queue, Vec};
+
+use crate::{
+    collision::detect_collison,
+    enemy::{Enemy, EnemyKind},
+    input::MenuStick,
+    level,
+    player::{Player, PLAYER_LIVES},
+    powerup::{PowerUp, PowerUpKind},
+    rng::{GameRng, XorShift32},
+};
+
+// Points for each hit; destroying an enemy also awards its hit points
+const HIT_SCORE: u32 = 1;
+// Bonus for destroying a boss, which also gives back a lost life
+const BOSS_REWARD: u32 = 100;
+const MAX_EVENTS: usize = 16;
+// The intermission after a level ends on its own after this many frames, and
+// ignores the button for the first few so a burst of fire doesn't skip it
+const INTERMISSION_FRAMES: u32 = 150;
+const INTERMISSION_MIN_FRAMES: u32 = 30;
+// While none is on screen, a power-up shows up with a chance of 1 in this per
+// frame
+const POWER_UP_CHANCE: u32 = 400;
+// Power-ups keep clear of the HUD at the top and bottom of the screen
+const POWER_UP_MARGIN: i32 = 16;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum GameState {
+    Menu,
+    Playing,
+    Paused,
+    LevelCompleted,
+    Dead,
+}
+
+/// Controls as sampled at the start of a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Input {
+    /// Horizontal control, see [`PLAYER_X_AXIS`](crate::player::PLAYER_X_AXIS).
+    pub x_axis: i8,
+    /// Vertical control, see [`PLAYER_Y_AXIS`](crate::player::PLAYER_Y_AXIS).
+    pub y_axis: i8,
+    /// The main button was pressed since the last frame.
+    pub fire: bool,
+    /// The main button is held down, which keeps firing.
+    pub fire_held: bool,
+    /// The pause button was pressed since the last frame. Firing can't
+    /// pause, however fast the main button is tapped.
+    pub pause: bool,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Event {
+    /// The player fired a bullet.
+    Shot,
+    /// A player bullet hit the enemy.
+    EnemyHit,
+    /// The enemy ran out of hit points.
+    EnemyDestroyed,
+    /// The boss ran out of hit points, the reward was handed out.
+    BossDefeated,
+    /// An enemy bullet, the boss laser or the enemy itself hit the player.
+    PlayerHit,
+    /// The shield took a hit in place of the player.
+    ShieldHit,
+    /// The player collected a power-up.
+    PowerUp(PowerUpKind),
+    /// A player bullet and an enemy bullet destroyed each other.
+    BulletsCollided,
+    /// The given level was completed, the intermission starts.
+    LevelCompleted(u32),
+    /// The level went up to the given value.
+    LevelUp(u32),
+    /// The player ran out of lives.
+    Died,
+    /// Sound was switched on or off from the pause menu.
+    ToggleSound,
+}
+
+/// Entries of the pause menu, top to bottom.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum PauseOption {
+    Resume,
+    Restart,
+    Sound,
+    MainMenu,
+}
+
+impl PauseOption {
+    pub const ALL: [PauseOption; 4] = [
+        PauseOption::Resume,
+        PauseOption::Restart,
+        PauseOption::Sound,
+        PauseOption::MainMenu,
+    ];
+}
+
+/// Selection in the pause menu, moved with the stick.
+#[derive(Clone, Copy, Default)]
+struct PauseMenu {
+    selected: usize,
+    stick: MenuStick,
+}
+
+impl PauseMenu {
+    /// Advances by one frame. Returns the option confirmed with the main
+    /// button, if any.
+    fn step(&mut self, input: Input) -> Option<PauseOption> {
+        if input.fire {
+            return Some(PauseOption::ALL[self.selected]);
+        }
+
+        let count = PauseOption::ALL.len();
+        match self.stick.step(input.y_axis) {
+            -1 => self.selected = (self.selected + count - 1) % count,
+            1 => self.selected = (self.selected + 1) % count,
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// What the player did in the current level, or the one just completed
+/// during the intermission.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct LevelStats {
+    pub shots: u32,
+    pub hits: u32,
+}
+
+impl LevelStats {
+    /// Share of shots that hit the enemy, in percent.
+    pub fn accuracy(&self) -> u32 {
+        if self.shots == 0 {
+            return 0;
+        }
+        (self.hits * 100 / self.shots).min(100)
+    }
+}
+
+/// Events produced by a single [`World::step`].
+///
+/// Events beyond the capacity are dropped; they only drive sound effects.
+pub type Events = Vec<Event, MAX_EVENTS>;
+
+pub struct World {
+    state: GameState,
+    score: u32,
+    level: u32,
+    stats: LevelStats,
+    // Score when the current level started
+    level_start_score: u32,
+    // Frames spent in the current intermission
+    intermission_frames: u32,
+    pause_menu: PauseMenu,
+    pub(crate) player: Player,
+    pub(crate) enemy: Enemy,
+    pub(crate) power_up: Option<PowerUp>,
+    screen_width: i32,
+    screen_height: i32,
+    rng: XorShift32,
+    // RNG state when the current run started
+    seed: u32,
+}
+
+impl World {
+    pub fn new(screen_width: i32, screen_height: i32, rng: XorShift32) -> Self {
+        Self {
+            seed: rng.state(),
+            state: GameState::Menu,
+            score: 0,
+            level: 1,
+            stats: LevelStats::default(),
+            level_start_score: 0,
+            intermission_frames: 0,
+            pause_menu: PauseMenu::default(),
+            player: Player::new(screen_width, screen_height),
+            enemy: Enemy::new(
+                level::def(1).opening_enemy(),
+                1,
+                screen_width,
+                screen_height,
+            ),
+            power_up: None,
+            screen_width,
+            screen_height,
+            rng,
+        }
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn stats(&self) -> LevelStats {
+        self.stats
+    }
+
+    pub fn lives(&self) -> u8 {
+        self.player.lives
+    }
+
+    /// Option highlighted in the pause menu.
+    pub fn pause_selection(&self) -> PauseOption {
+        PauseOption::ALL[self.pause_menu.selected]
+    }
+
+    /// Seed that reproduces the current run.
+    ///
+    /// A world created with `XorShift32::new(seed)` and fed the same inputs
+    /// from the menu onwards plays out identically.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    fn reset(&mut self) {
+        self.score = 0;
+        self.level = 1;
+        self.stats = LevelStats::default();
+        self.level_start_score = 0;
+        self.seed = self.rng.state();
+        self.player = Player::new(self.screen_width, self.screen_height);
+        self.enemy = Enemy::new(
+            level::def(1).opening_enemy(),
+            1,
+            self.screen_width,
+            self.screen_height,
+        );
+        self.power_up = None;
+    }
+
+    /// Advances the game by one frame.
+    pub fn step(&mut self, input: Input) -> Events {
+        let mut events = Events::new();
+
+        match self.state {
+            GameState::Menu => {
+                if input.fire {
+                    self.reset();
+                    self.state = GameState::Playing;
+                }
+            }
+            GameState::Playing if input.pause => {
+                self.state = GameState::Paused;
+                self.pause_menu = PauseMenu::default();
+            }
+            GameState::Playing => {
+                if (input.fire || input.fire_held) && self.player.shoot() {
+                    self.stats.shots += 1;
+                    push(&mut events, Event::Shot);
+                }
+                let target = self.player.img.bounding_box().center();
+                self.enemy.update(target, &mut self.rng);
+                // The wreck of the boss stays until the level is over
+                if self.enemy.is_destroyed() && self.enemy.kind() != EnemyKind::Boss {
+                    self.spawn_enemy();
+                }
+                self.player.update(input.x_axis, input.y_axis);
+                self.update_power_up();
+                self.power_up_collison(&mut events);
+                self.enemy_collison(&mut events);
+                self.ram_collison(&mut events);
+                self.laser_collison(&mut events);
+                self.player_collison(&mut events);
+                self.bullets_collison(&mut events);
+                if self.state == GameState::Playing {
+                    self.level_handle(&mut events);
+                }
+            }
+            GameState::Paused if input.pause => self.state = GameState::Playing,
+            GameState::Paused => match self.pause_menu.step(input) {
+                Some(PauseOption::Resume) => self.state = GameState::Playing,
+                Some(PauseOption::Restart) => {
+                    self.reset();
+                    self.state = GameState::Playing;
+                }
+                Some(PauseOption::Sound) => push(&mut events, Event::ToggleSound),
+                Some(PauseOption::MainMenu) => self.state = GameState::Menu,
+                None => {}
+            },
+            GameState::LevelCompleted => {
+                self.intermission_frames += 1;
+                let skipped = input.fire && self.intermission_frames >= INTERMISSION_MIN_FRAMES;
+                if skipped || self.intermission_frames >= INTERMISSION_FRAMES {
+                    self.next_level(&mut events);
+                }
+            }
+            GameState::Dead => {
+                if input.fire {
+                    self.state = GameState::Menu;
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Starts the intermission once enough points were scored in the level,
+    /// or once the boss is destroyed in a boss level.
+    fn level_handle(&mut self, events: &mut Events) {
+        let def = level::def(self.level);
+        let completed = if def.boss {
+            self.enemy.kind() == EnemyKind::Boss && self.enemy.is_destroyed()
+        } else {
+            self.score - self.level_start_score >= def.score
+        };
+        if !completed {
+            return;
+        }
+
+        self.state = GameState::LevelCompleted;
+        self.intermission_frames = 0;
+        self.player.bullets = Queue::new();
+        self.enemy.bullets = Queue::new();
+        self.power_up = None;
+        push(events, Event::LevelCompleted(self.level));
+    }
+
+    /// Ends the intermission and moves on to the next row of the level
+    /// table.
+    fn next_level(&mut self, events: &mut Events) {
+        self.level += 1;
+        self.level_start_score = self.score;
+        self.stats = LevelStats::default();
+        self.state = GameState::Playing;
+        self.player.set_level(self.level);
+
+        // The enemy opening the level takes over, unless it is already
+        // there. An enemy being destroyed is replaced at the new level once
+        // its explosion ends.
+        let kind = level::def(self.level).opening_enemy();
+        if self.enemy.is_alive() && kind == self.enemy.kind() {
+            self.enemy.level_up(self.level);
+        } else if self.enemy.is_alive() || self.enemy.is_destroyed() {
+            self.enemy = Enemy::new(kind, self.level, self.screen_width, self.screen_height);
+        }
+        push(events, Event::LevelUp(self.level));
+    }
+
+    /// Replaces the destroyed enemy with one of those of the level, or with
+    /// the boss.
+    fn spawn_enemy(&mut self) {
+        let def = level::def(self.level);
+        let kind = if def.boss {
+            EnemyKind::Boss
+        } else {
+            def.enemies[self.rng.next_u32() as usize % def.enemies.len()]
+        };
+        self.enemy = Enemy::new(kind, self.level, self.screen_width, self.screen_height);
+    }
+
+    /// Moves the power-up on screen, or maybe sends one in.
+    fn update_power_up(&mut self) {
+        if let Some(power_up) = self.power_up.as_mut() {
+            power_up.update();
+            if power_up.is_gone() {
+                self.power_up = None;
+            }
+            return;
+        }
+
+        if self.rng.next_u32() % POWER_UP_CHANCE != 0 {
+            return;
+        }
+
+        let kinds = PowerUpKind::ALL;
+        let kind = kinds[self.rng.next_u32() as usize % kinds.len()];
+        let range = (self.screen_height - 2 * POWER_UP_MARGIN).max(1) as u32;
+        let y = POWER_UP_MARGIN + (self.rng.next_u32() % range) as i32;
+        self.power_up = Some(PowerUp::new(kind, y, self.screen_width));
+    }
+
+    fn power_up_collison(&mut self, events: &mut Events) {
+        let Some(power_up) = self.power_up.as_ref() else {
+            return;
+        };
+
+        if detect_collison(power_up.bounding_box(), self.player.img.bounding_box()) {
+            let kind = power_up.kind();
+            self.player.power_up(kind);
+            self.power_up = None;
+            push(events, Event::PowerUp(kind));
+        }
+    }
+
+    /// Costs the player a life, unless their shield takes the hit. Either
+    /// way nothing can hit them for a while.
+    fn hit_player(&mut self, events: &mut Events) {
+        if self.player.absorb_hit() {
+            push(events, Event::ShieldHit);
+        } else {
+            self.player.lives = self.player.lives.saturating_sub(1);
+            push(events, Event::PlayerHit);
+        }
+        self.player.make_invincible();
+    }
+
+    fn enemy_collison(&mut self, events: &mut Events) {
+        let mut new_queue = Queue::new();
+
+        while let Some(bullet) = self.player.bullets.dequeue() {
+            // Bullets fly through the wreck of a destroyed enemy
+            if self.enemy.is_alive() && self.enemy.is_hit_by(bullet.shape.into()) {
+                self.score += HIT_SCORE;
+                push(events, Event::EnemyHit);
+                self.stats.hits += 1;
+                if self.enemy.hit() {
+                    self.score += self.enemy.max_hp();
+                    push(events, Event::EnemyDestroyed);
+                    if self.enemy.kind() == EnemyKind::Boss {
+                        self.score += BOSS_REWARD;
+                        self.player.lives = (self.player.lives + 1).min(PLAYER_LIVES);
+                        push(events, Event::BossDefeated);
+                    }
+                }
+            } else {
+                new_queue.enqueue(bullet).unwrap();
+            }
+        }
+
+        self.player.bullets = new_queue;
+    }
+
+    fn ram_collison(&mut self, events: &mut Events) {
+        if self.player.is_invincible() {
+            return;
+        }
+
+        if self.enemy.is_alive() && self.player.is_hit_by(self.enemy.bounding_box().into()) {
+            self.enemy.retreat();
+            self.hit_player(events);
+        }
+    }
+
+    fn laser_collison(&mut self, events: &mut Events) {
+        if self.player.is_invincible() {
+            return;
+        }
+        let Some(beam) = self.enemy.laser() else {
+            return;
+        };
+
+        if self.player.is_hit_by(beam.into()) {
+            self.enemy.laser_landed();
+            self.hit_player(events);
+        }
+    }
+
+    fn player_collison(&mut self, events: &mut Events) {
+        let mut new_queue = Queue::new();
+
+        while let Some(bullet) = self.enemy.bullets.dequeue() {
+            // Bullets fly through an invincible player
+            if !self.player.is_invincible() && self.player.is_hit_by(bullet.shape.into()) {
+                self.hit_player(events);
+            } else {
+                new_queue.enqueue(bullet).unwrap();
+            }
+        }
+
+        self.enemy.bullets = new_queue;
+
+        if self.player.lives == 0 {
+            self.state = GameState::Dead;
+            push(events, Event::Died);
+        }
+    }
+
+    fn bullets_collison(&mut self, events: &mut Events) {
+        let mut new_player_bullets = Queue::new();
+        let mut new_enemy_bullets = self.enemy.bullets.clone();
+
+        // Collect bullets that survived collision check
+        while let Some(player_bullet) = self.player.bullets.dequeue() {
+            let mut collided = false;
+
+            let mut tmp_enemy_bullets = Queue::new();
+            // Check for collisions with any enemy bullet
+            while let Some(enemy_bullet) = new_enemy_bullets.dequeue() {
+                if detect_collison(player_bullet.bounding_box(), enemy_bullet.bounding_box()) {
+                    collided = true;
+                    push(events, Event::BulletsCollided);
+                } else {
+                    tmp_enemy_bullets.enqueue(enemy_bullet).unwrap();
+                }
+            }
+
+            new_enemy_bullets = tmp_enemy_bullets;
+
+            if !collided {
+                new_player_bullets.enqueue(player_bullet).unwrap();
+            }
+        }
+
+        self.player.bullets = new_player_bullets;
+        self.enemy.bullets = new_enemy_bullets;
+    }
+}
+
+fn push(events: &mut Events, event: Event) {
+    // A full queue only loses a sound effect
+    let _ = events.push(event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRE: Input = Input {
+        x_axis: 0,
+        y_axis: 0,
+        fire: true,
+        fire_held: false,
+        pause: false,
+    };
+    const IDLE: Input = Input {
+        x_axis: 0,
+        y_axis: 0,
+        fire: false,
+        fire_held: false,
+        pause: false,
+    };
+
+    fn playing_world() -> World {
+        let mut world = World::new(128, 64, XorShift32::new(1));
+        world.step(FIRE);
+        assert_eq!(world.state(), GameState::Playing);
+        world
+    }
+
+    /// Moves the enemy so its centre lands on `target`.
+    fn move_enemy_to(world: &mut World, target: Point) {
+        let offset = target - world.enemy.img.bounding_box().center();
+        world.enemy.img = world.enemy.img.translate(offset);
+    }
+
+    #[test]
+    fn same_seed_and_input_play_out_the_same() {
+        let mut worlds = [1, 2].map(|_| World::new(128, 64, XorShift32::new(42)));
+        let mut enemy_bullets = 0;
+
+        for frame in 0..1000 {
+            let input = Input {
+                y_axis: if frame / 30 % 2 == 0 { 80 } else { -80 },
+                fire: frame % 7 == 0,
+                ..IDLE
+            };
+            for world in &mut worlds {
+                world.step(input);
+            }
+
+            let [a, b] = &worlds;
+            assert_eq!(
+                a.enemy.bounding_box(),
+                b.enemy.bounding_box(),
+                "frame {frame}"
+            );
+            let bullets = |world: &World| world.enemy.bullets.iter().map(|b| b.shape).collect();
+            let (a_bullets, b_bullets): (Vec<_, 32>, Vec<_, 32>) = (bullets(a), bullets(b));
+            assert_eq!(a_bullets, b_bullets, "frame {frame}");
+            enemy_bullets += a_bullets.len();
+        }
+
+        assert!(enemy_bullets > 0);
+    }
+
+    #[test]
+    fn bullet_hitting_the_enemy_scores() {
+        let mut world = playing_world();
+        let player = world.player.img.bounding_box();
+        let muzzle = player.top_left + Point::new(player.size.width as i32, 8);
+        move_enemy_to(&mut world, muzzle + Point::new(4, 0));
+
+        let events = world.step(FIRE);
+
+        assert!(events.contains(&Event::Shot));
+        assert!(events.contains(&Event::EnemyHit));
+        assert!(world.score() >= HIT_SCORE);
+        assert_eq!(world.stats().hits, 1);
+    }
+
+    #[test]
+    fn reaching_the_level_score_levels_up() {
+        let mut world = playing_world();
+        world.score = level::def(1).score;
+
+        let events = world.step(IDLE);
+        assert!(events.contains(&Event::LevelCompleted(1)));
+        assert_eq!(world.state(), GameState::LevelCompleted);
+
+        let mut events = Events::new();
+        for _ in 0..INTERMISSION_FRAMES {
+            events = world.step(IDLE);
+            if world.state() != GameState::LevelCompleted {
+                break;
+            }
+        }
+        assert!(events.contains(&Event::LevelUp(2)));
+        assert_eq!(world.state(), GameState::Playing);
+        assert_eq!(world.level(), 2);
+    }
+
+    #[test]
+    fn losing_the_last_life_ends_the_run() {
+        let mut world = playing_world();
+        world.player.lives = 1;
+        let player = world.player.img.bounding_box().center();
+        move_enemy_to(&mut world, player);
+
+        let events = world.step(IDLE);
+
+        assert!(events.contains(&Event::PlayerHit));
+        assert!(events.contains(&Event::Died));
+        assert_eq!(world.state(), GameState::Dead);
+        assert_eq!(world.lives(), 0);
+    }
+}